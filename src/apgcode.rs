@@ -0,0 +1,179 @@
+use crate::coord::Coord;
+use crate::pattern;
+use crate::period;
+use crate::rule::Rule;
+use crate::universe::Universe;
+
+// apgcodes as used by apgsearch and Catagolue, e.g. xs4_33, xp2_7 or xq4_153: a prefix for still lifes (xs and the
+// population), oscillators (xp and the period) and spaceships (xq and the period), then the extended Wechsler
+// encoding of the phase and orientation with the shortest, then alphabetically first, encoding.
+const DIGITS: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+const STRIP_HEIGHT: i32 = 5;
+
+// Extended Wechsler encoding of the cells relative to their bounding box. The pattern is cut into strips of five
+// rows, each column of a strip is one character with the top row as the lowest bit. Strips are separated by z,
+// runs of empty columns are shortened to w (2), x (3) and y followed by a digit (4 to 39), trailing ones dropped.
+pub fn wechsler(cells: &[Coord]) -> String {
+    let cells = pattern::normalize(cells);
+    let (width, height) = match pattern::bounding_box(&cells) { Some((_, max)) => (max.x + 1, max.y + 1), None => return String::new() };
+    let strips = (height + STRIP_HEIGHT - 1) / STRIP_HEIGHT;
+    let mut columns = vec![vec![0u8; width as usize]; strips as usize];
+    for c in &cells {
+        columns[(c.y / STRIP_HEIGHT) as usize][c.x as usize] |= 1 << (c.y % STRIP_HEIGHT);
+    }
+
+    let mut encoded = String::new();
+    for (i, strip) in columns.iter().enumerate() {
+        if i > 0 { encoded.push('z') }
+        let mut zeros = 0;
+        for column in strip {
+            if *column == 0 {
+                zeros += 1;
+                continue
+            }
+            push_zeros(&mut encoded, zeros);
+            zeros = 0;
+            encoded.push(DIGITS[*column as usize] as char);
+        }
+    }
+    encoded
+}
+
+fn push_zeros(encoded: &mut String, mut zeros: usize) {
+    while zeros > 0 {
+        let run = zeros.min(39);
+        match run {
+            1 => encoded.push('0'),
+            2 => encoded.push('w'),
+            3 => encoded.push('x'),
+            _ => {
+                encoded.push('y');
+                encoded.push(DIGITS[run - 4] as char);
+            }
+        }
+        zeros -= run;
+    }
+}
+
+// The shortest, then alphabetically first, encoding of the eight rotations and reflections.
+fn best_orientation(cells: &[Coord]) -> String {
+    let mut orientation = cells.to_vec();
+    let mut best: Option<String> = None;
+    for i in 0..8 {
+        if i == 4 { orientation = pattern::flip_horizontal(&orientation) }
+        let encoded = wechsler(&orientation);
+        let shorter = |b: &String| (encoded.len(), &encoded) < (b.len(), b);
+        if best.as_ref().is_none_or(shorter) { best = Some(encoded) }
+        orientation = pattern::rotate_cw(&orientation);
+    }
+    best.unwrap_or_default()
+}
+
+// apgcode of a periodic pattern, looking at most max_generations ahead for its period. Patterns that only become
+// periodic after a while are encoded as what they settle into.
+pub fn encode(cells: &[Coord], rule: &Rule, max_generations: u64) -> Result<String, String> {
+    let mut universe: Universe = cells.iter().copied().collect();
+    let periodicity = period::detect(&universe, rule, max_generations)
+        .ok_or(format!("no period found within {} generations", max_generations))?;
+    if periodicity.population == 0 { return Err("pattern dies out".to_string()) }
+
+    for _ in 0..periodicity.start {
+        universe = universe.step(rule);
+    }
+    let mut best: Option<String> = None;
+    for _ in 0..periodicity.period {
+        let encoded = best_orientation(&universe.iter().collect::<Vec<_>>());
+        if best.as_ref().is_none_or(|b| (encoded.len(), &encoded) < (b.len(), b)) { best = Some(encoded) }
+        universe = universe.step(rule);
+    }
+
+    let prefix = match periodicity.kind() {
+        "spaceship" => format!("xq{}", periodicity.period),
+        "oscillator" => format!("xp{}", periodicity.period),
+        _ => format!("xs{}", periodicity.population),
+    };
+    Ok(format!("{}_{}", prefix, best.unwrap_or_default()))
+}
+
+// Cells of an apgcode, with the top left corner of its bounding box at (0, 0).
+pub fn decode(code: &str) -> Result<Vec<Coord>, String> {
+    let (prefix, encoded) = code.split_once('_').ok_or(format!("not an apgcode: {}", code))?;
+    if !["xs", "xp", "xq"].iter().any(|p| prefix.starts_with(p)) || prefix[2..].parse::<u64>().is_err() {
+        return Err(format!("unsupported apgcode prefix: {}", prefix))
+    }
+
+    let digit = |c: char| DIGITS.iter().position(|d| *d as char == c);
+    let mut cells = Vec::new();
+    let (mut x, mut y) = (0, 0);
+    let mut chars = encoded.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            'z' => {
+                x = 0;
+                y += STRIP_HEIGHT;
+            },
+            'w' => x += 2,
+            'x' => x += 3,
+            'y' => {
+                let run = chars.next().and_then(digit).ok_or(format!("invalid apgcode: {}", code))?;
+                x += 4 + run as i32;
+            },
+            _ => {
+                let column = digit(c).filter(|d| *d < 32).ok_or(format!("invalid character in apgcode: {}", c))?;
+                for bit in 0..STRIP_HEIGHT {
+                    if column & (1 << bit) != 0 { cells.push(Coord::new(x, y + bit)) }
+                }
+                x += 1;
+            }
+        }
+    }
+    Ok(pattern::normalize(&cells))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decoded(code: &str) -> Vec<(i32, i32)> {
+        let mut cells: Vec<(i32, i32)> = decode(code).unwrap().iter().map(|c| (c.x, c.y)).collect();
+        cells.sort();
+        cells
+    }
+
+    #[test]
+    fn decodes_known_objects() {
+        assert_eq!(decoded("xs4_33"), vec![(0, 0), (0, 1), (1, 0), (1, 1)]);
+        assert_eq!(decoded("xp2_7"), vec![(0, 0), (0, 1), (0, 2)]);
+        assert_eq!(decoded("xq4_153"), vec![(0, 0), (1, 0), (1, 2), (2, 0), (2, 1)]);
+    }
+
+    #[test]
+    fn round_trips() {
+        let rule = Rule::life();
+        for code in ["xs4_33", "xs6_696", "xs7_2596", "xp2_7", "xp2_318c", "xq4_153", "xq4_6frc", "xs8_6996"] {
+            let cells = decode(code).unwrap();
+            assert_eq!(encode(&cells, &rule, 100).unwrap(), code);
+        }
+    }
+
+    #[test]
+    fn encodes_any_orientation() {
+        let rule = Rule::life();
+        let vertical = [Coord::new(5, 5), Coord::new(5, 6), Coord::new(5, 7)];
+        let horizontal = [Coord::new(-1, 3), Coord::new(0, 3), Coord::new(1, 3)];
+        assert_eq!(encode(&vertical, &rule, 10).unwrap(), "xp2_7");
+        assert_eq!(encode(&horizontal, &rule, 10).unwrap(), "xp2_7");
+    }
+
+    #[test]
+    fn rejects_malformed_codes() {
+        for code in ["", "33", "xs4", "ab4_33", "xs_33", "xsx_33", "xs4_3?", "xs4_3y", "xs4_é", "é_33"] {
+            assert!(decode(code).is_err(), "{} should not decode", code);
+        }
+    }
+
+    #[test]
+    fn fails_to_encode_dying_patterns() {
+        assert!(encode(&[Coord::new(0, 0)], &Rule::life(), 10).is_err());
+    }
+}
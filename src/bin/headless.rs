@@ -0,0 +1,284 @@
+use std::env;
+use std::fs;
+use std::process;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use game_of_rust::{config::Config, soup::{self, Tally}, stabilization::StabilizationDetector, population::{self, Sample}, emission::EmissionTracker, predecessor::{self, PredecessorResult}, search::{self, SearchOptions, Symmetry}, coord::Coord, rle, period, apgcode, census::{self, CensusOptions, Connectivity}, rule::Rule, universe::Universe};
+
+const USAGE: &str = "usage:
+  headless period <file> [max generations]
+  headless census <file> [generations] [--orthogonal] [--merge]
+  headless apgcode <file> [max generations]
+  headless decode <apgcode>
+  headless stabilize <file> [max generations]
+  headless population <file> <generations> [--out <csv file>]
+  headless emissions <file> <generations> [--delete <distance>] [--every <generations>]
+  headless predecessor <file> [--margin <cells>] [--exact] [--conflicts <limit>]
+  headless search <width> <height> [--period <p>] [--symmetry <C1|C2|C4|D2|D4|D8>] [--rule <rule>] [--out <dir>] [--max <results>] [--conflicts <limit>]
+  headless soup [count] [seed] [--out <dir>] [--rare <threshold>] [--rule <rule>]";
+const MAX_GENERATIONS: u64 = 10000;
+const SOUPS: u64 = 1000;
+const PROGRESS_INTERVAL: u64 = 1000;
+
+// Pattern analysis without opening a window.
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(|a| a.as_str()).collect();
+
+    let result = match args.as_slice() {
+        ["period", path, rest @ ..] => period(path, rest),
+        ["census", path, rest @ ..] => census(path, rest),
+        ["apgcode", path, rest @ ..] => apgcode(path, rest),
+        ["decode", code] => decode(code),
+        ["stabilize", path, rest @ ..] => stabilize(path, rest),
+        ["population", path, rest @ ..] => population(path, rest),
+        ["emissions", path, rest @ ..] => emissions(path, rest),
+        ["predecessor", path, rest @ ..] => predecessor(path, rest),
+        ["search", width, height, rest @ ..] => search(width, height, rest),
+        ["soup", rest @ ..] => soup(rest),
+        _ => Err(USAGE.to_string())
+    };
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
+
+fn read(path: &str) -> Result<(Universe, Rule), String> {
+    let contents = fs::read_to_string(path).map_err(|e| format!("could not open {}: {}", path, e))?;
+    let cells = rle::parse_pattern(&contents)?;
+    Ok((cells.into_iter().collect(), rle::parse_rule(&contents).unwrap_or_else(Rule::life)))
+}
+
+fn number_arg(args: &[&str], index: usize, default: u64) -> Result<u64, String> {
+    match args.get(index) {
+        Some(arg) => arg.parse().map_err(|_| format!("not a number: {}", arg)),
+        None => Ok(default)
+    }
+}
+
+fn period(path: &str, args: &[&str]) -> Result<(), String> {
+    let (universe, rule) = read(path)?;
+    let max_generations = number_arg(args, 0, MAX_GENERATIONS)?;
+    match period::detect(&universe, &rule, max_generations) {
+        Some(periodicity) => {
+            println!("{}", periodicity.describe());
+            println!("period {}", periodicity.period);
+            println!("displacement {} {}", periodicity.dx, periodicity.dy);
+            if let Some(speed) = periodicity.speed() { println!("speed {}", speed) }
+            println!("cycle starts at generation {}", periodicity.start);
+        },
+        None => println!("no period found within {} generations", max_generations)
+    }
+    Ok(())
+}
+
+// Counts the objects left after running the pattern for the given number of generations.
+fn census(path: &str, args: &[&str]) -> Result<(), String> {
+    let (mut universe, rule) = read(path)?;
+    let (flags, args): (Vec<&str>, Vec<&str>) = args.iter().partition(|a| a.starts_with("--"));
+    let generations = number_arg(&args, 0, 0)?;
+    let mut options = CensusOptions { connectivity: Connectivity::Moore, merge_pseudo_objects: false };
+    for flag in flags {
+        match flag {
+            "--orthogonal" => options.connectivity = Connectivity::Orthogonal,
+            "--merge" => options.merge_pseudo_objects = true,
+            _ => return Err(format!("unknown option: {}\n{}", flag, USAGE))
+        }
+    }
+
+    for _ in 0..generations {
+        universe = universe.step(&rule);
+    }
+    for (name, count) in census::census(&universe, &rule, &options) {
+        println!("{} {}", count, name);
+    }
+    Ok(())
+}
+
+fn apgcode(path: &str, args: &[&str]) -> Result<(), String> {
+    let (universe, rule) = read(path)?;
+    let max_generations = number_arg(args, 0, MAX_GENERATIONS)?;
+    let cells: Vec<Coord> = universe.iter().collect();
+    println!("{}", apgcode::encode(&cells, &rule, max_generations)?);
+    Ok(())
+}
+
+// Prints the pattern of an apgcode as RLE.
+fn decode(code: &str) -> Result<(), String> {
+    let cells = apgcode::decode(code)?;
+    print!("{}", rle::encode_pattern(&cells, &Rule::life()));
+    Ok(())
+}
+
+// Runs the pattern until it settles, reporting its lifespan and the spaceships it sends off.
+fn stabilize(path: &str, args: &[&str]) -> Result<(), String> {
+    let (mut universe, rule) = read(path)?;
+    let max_generations = number_arg(args, 0, MAX_GENERATIONS)?;
+    let mut detector = StabilizationDetector::new();
+    for generation in 0..=max_generations {
+        if let Some(stabilization) = detector.observe(generation, &universe, &rule) {
+            println!("lifespan {}", stabilization.lifespan);
+            println!("period {}", stabilization.period);
+            println!("final population {}", stabilization.population);
+            println!("escaping spaceships {}", stabilization.escapes.len());
+            if !stabilization.escapes.is_empty() { println!("{}", stabilization.describe_escapes()) }
+            return Ok(())
+        }
+        universe = universe.step(&rule);
+    }
+    println!("still active after {} generations", max_generations);
+    Ok(())
+}
+
+// Writes the population, births, deaths and bounding box size of every generation as CSV.
+fn population(path: &str, args: &[&str]) -> Result<(), String> {
+    let (mut universe, rule) = read(path)?;
+    let (out, args) = match args {
+        [rest @ .., "--out", out] => (Some(*out), rest),
+        _ => (None, args)
+    };
+    let generations = number_arg(args, 0, MAX_GENERATIONS)?;
+
+    let mut samples = vec![Sample::new(0, &universe, 0, 0)];
+    for generation in 1..=generations {
+        let next = universe.step(&rule);
+        samples.push(Sample::between(generation, &universe, &next));
+        universe = next;
+    }
+    let csv = population::to_csv(samples.iter());
+    match out {
+        Some(out) => fs::write(out, csv).map_err(|e| format!("could not write {}: {}", out, e)),
+        None => {
+            print!("{}", csv);
+            Ok(())
+        }
+    }
+}
+
+// Counts the spaceships a gun or other emitter sends out and works out its period from when they leave.
+fn emissions(path: &str, args: &[&str]) -> Result<(), String> {
+    let (mut universe, rule) = read(path)?;
+    let (mut delete_distance, mut check_interval) = (None, 1);
+    let mut numbers = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("missing value for {}", arg));
+        match *arg {
+            "--delete" => delete_distance = Some(number_arg(&[value()?], 0, 0)? as i32),
+            "--every" => check_interval = number_arg(&[value()?], 0, 1)?,
+            _ if arg.starts_with("--") => return Err(format!("unknown option: {}\n{}", arg, USAGE)),
+            _ => numbers.push(*arg)
+        }
+    }
+    let generations = number_arg(&numbers, 0, MAX_GENERATIONS)?;
+
+    let mut tracker = EmissionTracker::new(delete_distance, check_interval);
+    for generation in 0..=generations {
+        tracker.observe(generation, &mut universe, &rule);
+        if generation < generations { universe = universe.step(&rule) }
+    }
+    println!("{}", tracker.describe());
+    println!("final population {}", universe.len());
+    Ok(())
+}
+
+// Looks for a parent of the whole pattern, or proves that it is a Garden of Eden.
+fn predecessor(path: &str, args: &[&str]) -> Result<(), String> {
+    let (universe, rule) = read(path)?;
+    let (min, max) = universe.bounding_box().ok_or("the pattern is empty")?;
+    let mut options = Config::new().predecessor_options;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("missing value for {}", arg));
+        match *arg {
+            "--margin" => options.margin = number_arg(&[value()?], 0, 1)? as i32,
+            "--exact" => options.exact = true,
+            "--conflicts" => options.max_conflicts = number_arg(&[value()?], 0, 0)?,
+            _ => return Err(format!("unknown option: {}\n{}", arg, USAGE))
+        }
+    }
+
+    match predecessor::search(&universe, min, max, &rule, &options) {
+        PredecessorResult::Found(cells) => print!("{}", rle::encode_pattern(&cells, &rule)),
+        PredecessorResult::NoPredecessor if options.exact => println!("no predecessor within {} cells", options.margin),
+        PredecessorResult::NoPredecessor => println!("no predecessor: the pattern is a Garden of Eden"),
+        PredecessorResult::Unknown => println!("gave up after {} conflicts", options.max_conflicts),
+    }
+    Ok(())
+}
+
+// Looks for still lifes or oscillators filling a box, saving each one as it is found.
+fn search(width: &str, height: &str, args: &[&str]) -> Result<(), String> {
+    let config = Config::new();
+    let mut options = SearchOptions {
+        width: width.parse().map_err(|_| format!("not a number: {}", width))?,
+        height: height.parse().map_err(|_| format!("not a number: {}", height))?,
+        period: 1,
+        symmetry: Symmetry::None,
+        rule: Rule::life(),
+        max_conflicts: config.search_max_conflicts,
+    };
+    let (mut dir, mut max_results) = (config.search_dir.to_string(), config.search_max_results);
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("missing value for {}", arg));
+        match *arg {
+            "--period" => options.period = number_arg(&[value()?], 0, 1)?,
+            "--symmetry" => options.symmetry = Symmetry::parse(value()?)?,
+            "--rule" => options.rule = Rule::parse(value()?)?,
+            "--out" => dir = value()?.to_string(),
+            "--max" => max_results = number_arg(&[value()?], 0, 0)? as usize,
+            "--conflicts" => options.max_conflicts = number_arg(&[value()?], 0, 0)?,
+            _ => return Err(format!("unknown option: {}\n{}", arg, USAGE))
+        }
+    }
+
+    let start = Instant::now();
+    let summary = search::run(&options, max_results, &dir, |line| println!("{}", line))?;
+    println!("{} in {:.1}s", summary, start.elapsed().as_secs_f64());
+    Ok(())
+}
+
+// Searches random soups from consecutive seeds, tallying the objects they settle into and saving the rare ones.
+fn soup(args: &[&str]) -> Result<(), String> {
+    let config = Config::new();
+    let mut options = config.soup_options.clone();
+    let (mut dir, mut threshold) = (config.soup_dir.to_string(), config.soup_rare_threshold);
+    let mut numbers = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("missing value for {}", arg));
+        match *arg {
+            "--out" => dir = value()?.to_string(),
+            "--rare" => threshold = number_arg(&[value()?], 0, 0)?,
+            "--rule" => options.rule = Rule::parse(value()?)?,
+            _ if arg.starts_with("--") => return Err(format!("unknown option: {}\n{}", arg, USAGE)),
+            _ => numbers.push(*arg)
+        }
+    }
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let count = number_arg(&numbers, 0, SOUPS)?;
+    let first_seed = number_arg(&numbers, 1, now)?;
+
+    println!("searching {} soups from seed {}, rule {}", count, first_seed, options.rule);
+    let start = Instant::now();
+    let mut tally = Tally::new();
+    for seed in first_seed..first_seed + count {
+        tally.add(&soup::run(seed, &options));
+        if tally.soups.is_multiple_of(PROGRESS_INTERVAL) {
+            println!("{} soups, {:.1} soups/s", tally.soups, tally.soups as f64 / start.elapsed().as_secs_f64());
+        }
+    }
+
+    for (name, count) in tally.sorted() {
+        println!("{} {}", count, name);
+    }
+    if tally.unstable > 0 { println!("{} soups did not stabilize within {} generations", tally.unstable, options.max_generations) }
+    let rare = tally.rare(threshold);
+    if !rare.is_empty() {
+        let saved = soup::save_rare(&tally, &options, threshold, &dir)?;
+        println!("{} rare objects, saved {} soups to {}", rare.len(), saved, dir);
+    }
+    Ok(())
+}
@@ -1,4 +1,4 @@
-use game_of_rust::{config::Config, state::State, inputs, state_mgmt, rendering, rle};
+use game_of_rust::{config::Config, state::State, inputs, state_mgmt, rendering, rle, bindings::Bindings, library, session};
 use once_cell::sync::Lazy;
 
 fn main() -> Result<(), String> {
@@ -8,30 +8,32 @@ fn main() -> Result<(), String> {
     let mut config = Config::new();
     let window = video_subsystem.window("game-of-rust", config.window_width as u32, config.window_height as u32)
         .position_centered()
+        .resizable()
+        .allow_highdpi()
         .build()
         .expect("could not initialize video subsystem");
     let mut canvas = window.into_canvas().build()
         .expect("could not make a canvas");
+    let texture_creator = canvas.texture_creator();
+    let mut textures = rendering::Textures::new(&texture_creator);
 
     static ttf_context: Lazy<sdl2::ttf::Sdl2TtfContext> = Lazy::new(|| {
         sdl2::ttf::init().expect("could not create ttf context")
     });
     config.font = Some(ttf_context.load_font(config.font_path, 64)?);
+    config.bindings = Bindings::load(config.bindings_path);
+    config.pattern_dirs.extend(library::read_dirs(config.library_path));
     let mut event_pump: sdl2::EventPump = sdl_context.event_pump()?;
 
     // let mut state = state_mgmt::initial_state();
     let mut state = State::new();
-    /*
-    patterns:
-    p960_2c5gun
-    60P5H2V0
-    otcametapixel
-    gosperguninlineinverter
-    p82pihassler
-    simkinglidergunboatbitvariant
-    twogun
-     */
-    rle::load_pattern("./patterns/p960_2c5gun.rle", &mut state, 0, 0);
+    state.bookmarks = session::load(config.session_path);
+    // other patterns can be picked from the library browser (F2)
+    let pattern_path = "./patterns/p960_2c5gun.rle";
+    match rle::load_pattern(pattern_path, &mut state, 0, 0) {
+        Ok(()) => state.pattern_path = Some(pattern_path.to_string()),
+        Err(e) => println!("could not load pattern: {}", e)
+    }
 
     // https://gafferongames.com/post/fix_your_timestep/
     let mut accumulator = 0.0;
@@ -40,6 +42,7 @@ fn main() -> Result<(), String> {
         let frame_time = state.timestep.delta();
         accumulator += frame_time;
         inputs::handle_inputs(&mut state, &mut event_pump, &config);
+        if state.viewport_changed { rendering::update_viewport(&mut canvas, &mut state, &mut config)?; }
         state_mgmt::update_fps(&mut state, &config);
 
         while accumulator >= config.dt {
@@ -56,7 +59,7 @@ fn main() -> Result<(), String> {
 
         // render( state );
 
-        rendering::render(&mut canvas, &state, &config);
+        rendering::render(&mut canvas, &mut textures, &state, &config);
     }
 
     Ok(())
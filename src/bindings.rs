@@ -0,0 +1,399 @@
+use std::fmt;
+use std::fs;
+
+use sdl2::keyboard::{Keycode, Mod};
+use sdl2::mouse::MouseButton;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Action {
+    Quit,
+    ToggleFullscreen,
+    TogglePause,
+    SpeedUp,
+    SlowDown,
+    ResetCamera,
+    FollowCentroid,
+    FollowBoundingBox,
+    AutoFit,
+    GoTo,
+    GoToBookmark(usize),
+    SetBookmark(usize),
+    NextBookmark,
+    PreviousBookmark,
+    PanUp,
+    PanDown,
+    PanLeft,
+    PanRight,
+    ZoomIn,
+    ZoomOut,
+    Pan,
+    UseTool,
+    SelectTool,
+    LineTool,
+    RectangleTool,
+    EllipseTool,
+    StampTool,
+    StampClipboard,
+    CycleStampMode,
+    Copy,
+    Cut,
+    Paste,
+    SaveSelection,
+    LoadClipboard,
+    ClearSelection,
+    ClearOutsideSelection,
+    RandomFill,
+    RotateSelection,
+    FlipHorizontal,
+    FlipVertical,
+    ResetHistory,
+    ClearHistory,
+    ExportHistory,
+    CycleRenderMode,
+    ToggleMinimap,
+    ExportSvg,
+    ToggleHelp,
+    ToggleHud,
+    Census,
+    ToggleGraph,
+    ExportPopulation,
+    ToggleEmissions,
+    TogglePeriod,
+    FindPredecessor,
+    ToggleConsole,
+    ToggleLibrary,
+    PrintCells,
+    PrintCenter,
+    PrintFps,
+    PrintState,
+}
+
+// Every action with its name in the bindings file and its default bindings, in the order the help overlay lists them.
+// Bookmark actions are numbered 1 to 9 after the number keys they are bound to.
+pub const ACTIONS: &[(Action, &str, &[&str])] = &[
+    (Action::Quit, "quit", &["Escape"]),
+    (Action::ToggleFullscreen, "toggle_fullscreen", &["F11"]),
+    (Action::TogglePause, "toggle_pause", &["Space"]),
+    (Action::SpeedUp, "speed_up", &["+", "Shift+=", "Keypad +"]),
+    (Action::SlowDown, "slow_down", &["-", "Keypad -"]),
+    (Action::ResetCamera, "reset_camera", &["R"]),
+    (Action::FollowCentroid, "follow_centroid", &["K"]),
+    (Action::FollowBoundingBox, "follow_bounding_box", &["Shift+K"]),
+    (Action::AutoFit, "auto_fit", &["A"]),
+    (Action::GoTo, "go_to", &["Ctrl+G"]),
+    (Action::GoToBookmark(1), "go_to_bookmark_1", &["1"]),
+    (Action::GoToBookmark(2), "go_to_bookmark_2", &["2"]),
+    (Action::GoToBookmark(3), "go_to_bookmark_3", &["3"]),
+    (Action::GoToBookmark(4), "go_to_bookmark_4", &["4"]),
+    (Action::GoToBookmark(5), "go_to_bookmark_5", &["5"]),
+    (Action::GoToBookmark(6), "go_to_bookmark_6", &["6"]),
+    (Action::GoToBookmark(7), "go_to_bookmark_7", &["7"]),
+    (Action::GoToBookmark(8), "go_to_bookmark_8", &["8"]),
+    (Action::GoToBookmark(9), "go_to_bookmark_9", &["9"]),
+    (Action::SetBookmark(1), "set_bookmark_1", &["Ctrl+1"]),
+    (Action::SetBookmark(2), "set_bookmark_2", &["Ctrl+2"]),
+    (Action::SetBookmark(3), "set_bookmark_3", &["Ctrl+3"]),
+    (Action::SetBookmark(4), "set_bookmark_4", &["Ctrl+4"]),
+    (Action::SetBookmark(5), "set_bookmark_5", &["Ctrl+5"]),
+    (Action::SetBookmark(6), "set_bookmark_6", &["Ctrl+6"]),
+    (Action::SetBookmark(7), "set_bookmark_7", &["Ctrl+7"]),
+    (Action::SetBookmark(8), "set_bookmark_8", &["Ctrl+8"]),
+    (Action::SetBookmark(9), "set_bookmark_9", &["Ctrl+9"]),
+    (Action::NextBookmark, "next_bookmark", &["]"]),
+    (Action::PreviousBookmark, "previous_bookmark", &["["]),
+    (Action::PanUp, "pan_up", &["Up"]),
+    (Action::PanDown, "pan_down", &["Down"]),
+    (Action::PanLeft, "pan_left", &["Left"]),
+    (Action::PanRight, "pan_right", &["Right"]),
+    (Action::ZoomIn, "zoom_in", &["X"]),
+    (Action::ZoomOut, "zoom_out", &["Z"]),
+    (Action::Pan, "pan", &["MouseRight", "MouseMiddle"]),
+    (Action::UseTool, "use_tool", &["MouseLeft"]),
+    (Action::SelectTool, "select_tool", &["S"]),
+    (Action::LineTool, "line_tool", &["L"]),
+    (Action::RectangleTool, "rectangle_tool", &["B"]),
+    (Action::EllipseTool, "ellipse_tool", &["O"]),
+    (Action::StampTool, "stamp_tool", &["I"]),
+    (Action::StampClipboard, "stamp_clipboard", &["Ctrl+Shift+V"]),
+    (Action::CycleStampMode, "cycle_stamp_mode", &["G"]),
+    (Action::Copy, "copy", &["Ctrl+C"]),
+    (Action::Cut, "cut", &["Ctrl+X"]),
+    (Action::Paste, "paste", &["Ctrl+V"]),
+    (Action::SaveSelection, "save_selection", &["Ctrl+S"]),
+    (Action::LoadClipboard, "load_clipboard", &["Ctrl+O"]),
+    (Action::ClearSelection, "clear_selection", &["Delete"]),
+    (Action::ClearOutsideSelection, "clear_outside_selection", &["Shift+Delete"]),
+    (Action::RandomFill, "random_fill", &["N"]),
+    (Action::RotateSelection, "rotate_selection", &["T"]),
+    (Action::FlipHorizontal, "flip_horizontal", &["H"]),
+    (Action::FlipVertical, "flip_vertical", &["V"]),
+    (Action::ResetHistory, "reset_history", &["Y"]),
+    (Action::ClearHistory, "clear_history", &["Shift+Y"]),
+    (Action::ExportHistory, "export_history", &["Ctrl+Y"]),
+    (Action::CycleRenderMode, "cycle_render_mode", &["M"]),
+    (Action::ToggleMinimap, "toggle_minimap", &["Tab"]),
+    (Action::ExportSvg, "export_svg", &["E"]),
+    (Action::ToggleHelp, "toggle_help", &["F1"]),
+    (Action::ToggleHud, "toggle_hud", &["F3"]),
+    (Action::Census, "census", &["F4"]),
+    (Action::ToggleGraph, "toggle_graph", &["F5"]),
+    (Action::ExportPopulation, "export_population", &["Ctrl+E"]),
+    (Action::ToggleEmissions, "toggle_emissions", &["F6"]),
+    (Action::TogglePeriod, "toggle_period", &["F7"]),
+    (Action::FindPredecessor, "find_predecessor", &["Ctrl+P"]),
+    (Action::ToggleConsole, "toggle_console", &["`"]),
+    (Action::ToggleLibrary, "toggle_library", &["F2"]),
+    (Action::PrintCells, "print_cells", &["P"]),
+    (Action::PrintCenter, "print_center", &["C"]),
+    (Action::PrintFps, "print_fps", &["F"]),
+    (Action::PrintState, "print_state", &["D"]),
+];
+
+impl Action {
+    pub fn name(self) -> &'static str {
+        ACTIONS.iter().find(|(action, _, _)| *action == self).map_or("unknown", |(_, name, _)| name)
+    }
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        ACTIONS.iter().find(|(_, n, _)| *n == name).map(|(action, _, _)| *action)
+    }
+
+    // Actions that stay active for as long as their input is held down instead of firing once per press.
+    pub fn is_held(self) -> bool {
+        matches!(self, Action::PanUp | Action::PanDown | Action::PanLeft | Action::PanRight | Action::ZoomIn | Action::ZoomOut)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Input {
+    Key(Keycode),
+    Mouse(MouseButton),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub struct Modifiers {
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+}
+
+impl Modifiers {
+    // The command key counts as control so that the usual shortcuts work on macOS.
+    pub fn from_keymod(keymod: Mod) -> Modifiers {
+        Modifiers {
+            ctrl: keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD | Mod::LGUIMOD | Mod::RGUIMOD),
+            shift: keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD),
+            alt: keymod.intersects(Mod::LALTMOD | Mod::RALTMOD),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Binding {
+    pub input: Input,
+    pub modifiers: Modifiers,
+}
+
+impl Binding {
+    // Parses bindings such as "Space", "Ctrl+Shift+Y", "Keypad +" or "MouseRight", key names are SDL key names.
+    pub fn parse(binding: &str) -> Result<Binding, String> {
+        let mut modifiers = Modifiers::default();
+        let mut rest = binding.trim();
+        loop {
+            let lower = rest.to_lowercase();
+            if lower.starts_with("ctrl+") && rest.len() > 5 {
+                modifiers.ctrl = true;
+                rest = &rest[5..];
+            } else if lower.starts_with("shift+") && rest.len() > 6 {
+                modifiers.shift = true;
+                rest = &rest[6..];
+            } else if lower.starts_with("alt+") && rest.len() > 4 {
+                modifiers.alt = true;
+                rest = &rest[4..];
+            } else {
+                break
+            }
+        }
+
+        let input = match rest.to_lowercase().as_str() {
+            "mouseleft" => Input::Mouse(MouseButton::Left),
+            "mousemiddle" => Input::Mouse(MouseButton::Middle),
+            "mouseright" => Input::Mouse(MouseButton::Right),
+            "mousex1" => Input::Mouse(MouseButton::X1),
+            "mousex2" => Input::Mouse(MouseButton::X2),
+            _ => Input::Key(Keycode::from_name(rest).ok_or(format!("unknown key: {}", rest))?),
+        };
+
+        Ok(Binding { input, modifiers })
+    }
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.modifiers.ctrl { write!(f, "Ctrl+")? }
+        if self.modifiers.shift { write!(f, "Shift+")? }
+        if self.modifiers.alt { write!(f, "Alt+")? }
+        match self.input {
+            Input::Key(keycode) => write!(f, "{}", keycode.name()),
+            Input::Mouse(button) => write!(f, "Mouse{:?}", button),
+        }
+    }
+}
+
+// Maps keys and mouse buttons with modifiers to actions, in the order they were defined.
+#[derive(Default)]
+pub struct Bindings {
+    bindings: Vec<(Binding, Action)>,
+}
+
+impl Bindings {
+    pub fn new() -> Bindings {
+        Bindings { bindings: Vec::new() }
+    }
+
+    pub fn defaults() -> Bindings {
+        let mut bindings = Bindings::new();
+        for (action, name, defaults) in ACTIONS {
+            for binding in defaults.iter() {
+                match Binding::parse(binding) {
+                    Ok(binding) => bindings.bindings.push((binding, *action)),
+                    Err(e) => println!("invalid default binding for {}: {}", name, e),
+                }
+            }
+        }
+        bindings
+    }
+
+    // Loads the default bindings and replaces the bindings of every action listed in the file,
+    // one `action = binding` per line. Errors and conflicts are reported but not fatal.
+    pub fn load(path: &str) -> Bindings {
+        let mut bindings = Bindings::defaults();
+        if let Ok(contents) = fs::read_to_string(path) {
+            for error in bindings.apply(&contents) {
+                println!("{}: {}", path, error);
+            }
+        }
+        for conflict in bindings.conflicts() {
+            println!("{}: {}", path, conflict);
+        }
+        bindings
+    }
+
+    pub fn apply(&mut self, contents: &str) -> Vec<String> {
+        let mut errors = Vec::new();
+        let mut rebound: Vec<Action> = Vec::new();
+        for (i, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') { continue }
+            let (name, binding) = match line.split_once('=') {
+                Some((name, binding)) => (name.trim(), binding.trim()),
+                None => { errors.push(format!("line {}: expected action = binding", i + 1)); continue }
+            };
+            let action = match Action::from_name(name) {
+                Some(action) => action,
+                None => { errors.push(format!("line {}: unknown action: {}", i + 1, name)); continue }
+            };
+            let binding = match Binding::parse(binding) {
+                Ok(binding) => binding,
+                Err(e) => { errors.push(format!("line {}: {}", i + 1, e)); continue }
+            };
+            if !rebound.contains(&action) {
+                self.bindings.retain(|(_, a)| *a != action);
+                rebound.push(action);
+            }
+            self.bindings.push((binding, action));
+        }
+        errors
+    }
+
+    pub fn conflicts(&self) -> Vec<String> {
+        let mut conflicts = Vec::new();
+        for (i, (binding, action)) in self.bindings.iter().enumerate() {
+            for (other_binding, other_action) in &self.bindings[i + 1..] {
+                if binding == other_binding && action != other_action {
+                    conflicts.push(format!("{} is bound to both {} and {}, using {}", binding, action.name(), other_action.name(), action.name()));
+                }
+            }
+        }
+        conflicts
+    }
+
+    // Exact modifiers win, otherwise shift is ignored so that shifted keys still work.
+    pub fn action(&self, input: Input, modifiers: Modifiers) -> Option<Action> {
+        let unshifted = Modifiers { shift: false, ..modifiers };
+        self.find(input, modifiers).or_else(|| self.find(input, unshifted))
+    }
+
+    // Actions bound to the input with any modifiers, the modifiers may have changed by the time it is released.
+    pub fn actions_for(&self, input: Input) -> impl Iterator<Item = Action> + '_ {
+        self.bindings.iter().filter(move |(b, _)| b.input == input).map(|(_, a)| *a)
+    }
+
+    fn find(&self, input: Input, modifiers: Modifiers) -> Option<Action> {
+        self.bindings.iter().find(|(b, _)| b.input == input && b.modifiers == modifiers).map(|(_, a)| *a)
+    }
+
+    pub fn bindings_for(&self, action: Action) -> impl Iterator<Item = &Binding> {
+        self.bindings.iter().filter(move |(_, a)| *a == action).map(|(b, _)| b)
+    }
+
+    // Conflicts first, then one line per action listing its bindings, for the help overlay.
+    pub fn describe(&self) -> Vec<String> {
+        let actions = ACTIONS.iter().map(|(action, name, _)| {
+            let bindings: Vec<String> = self.bindings_for(*action).map(|b| b.to_string()).collect();
+            format!("{}  {}", name.replace('_', " "), bindings.join("  "))
+        });
+        self.conflicts().into_iter().chain(actions).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_modifiers_keys_and_mouse_buttons() {
+        let binding = Binding::parse("Ctrl+Shift+Y").unwrap();
+        assert_eq!(binding.input, Input::Key(Keycode::Y));
+        assert_eq!(binding.modifiers, Modifiers { ctrl: true, shift: true, alt: false });
+        assert_eq!(Binding::parse(" alt+space ").unwrap(), Binding { input: Input::Key(Keycode::Space), modifiers: Modifiers { alt: true, ..Modifiers::default() } });
+        assert_eq!(Binding::parse("Keypad +").unwrap().input, Input::Key(Keycode::KpPlus));
+        assert_eq!(Binding::parse("MouseRight").unwrap(), Binding { input: Input::Mouse(MouseButton::Right), modifiers: Modifiers::default() });
+        // a lone "+" is the key, not a modifier
+        assert_eq!(Binding::parse("Shift+=").unwrap().input, Input::Key(Keycode::Equals));
+        assert!(Binding::parse("Ctrl+").is_err());
+        assert!(Binding::parse("NoSuchKey").is_err());
+    }
+
+    #[test]
+    fn formats_bindings_the_way_they_are_parsed() {
+        for binding in ["Ctrl+Shift+V", "Alt+F4", "MouseMiddle", "Keypad +"] {
+            assert_eq!(Binding::parse(binding).unwrap().to_string(), binding);
+        }
+    }
+
+    #[test]
+    fn names_every_action_once_and_parses_every_default() {
+        for (i, (action, name, defaults)) in ACTIONS.iter().enumerate() {
+            assert_eq!(Action::from_name(name), Some(*action));
+            assert_eq!(action.name(), *name);
+            assert!(ACTIONS[i + 1..].iter().all(|(a, n, _)| a != action && n != name), "{} is listed twice", name);
+            assert!(defaults.iter().all(|b| Binding::parse(b).is_ok()), "invalid default binding for {}", name);
+        }
+        assert!(Bindings::defaults().conflicts().is_empty());
+    }
+
+    #[test]
+    fn rebinds_actions_and_reports_conflicts() {
+        let mut bindings = Bindings::defaults();
+        let errors = bindings.apply("# comment\nquit = Q\nquit = Ctrl+Q\nzoom_in = P\nnot_an_action = A\nspeed_up\n");
+        assert_eq!(errors.len(), 2);
+        assert_eq!(bindings.action(Input::Key(Keycode::Q), Modifiers::default()), Some(Action::Quit));
+        assert_eq!(bindings.action(Input::Key(Keycode::Escape), Modifiers::default()), None);
+        assert_eq!(bindings.bindings_for(Action::Quit).count(), 2);
+        // P was already bound to print_cells, which is defined first and wins
+        assert_eq!(bindings.conflicts().len(), 1);
+        assert_eq!(bindings.action(Input::Key(Keycode::P), Modifiers::default()), Some(Action::PrintCells));
+        assert!(bindings.describe()[0].contains("bound to both"));
+    }
+}
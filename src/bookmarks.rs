@@ -0,0 +1,92 @@
+use crate::camera::{self, View};
+use crate::config::Config;
+use crate::session;
+use crate::state::State;
+
+// Named camera position and zoom, kept per pattern file.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Bookmark {
+    pub pattern: String,
+    pub name: String,
+    pub view: View,
+    // Number key the bookmark was saved with, if any.
+    pub slot: Option<usize>,
+}
+
+fn pattern_key(state: &State) -> String {
+    state.pattern_path.clone().unwrap_or_default()
+}
+
+// Indices into state.bookmarks of the current pattern's bookmarks, in the order they were added.
+pub fn current(state: &State) -> Vec<usize> {
+    let pattern = pattern_key(state);
+    (0..state.bookmarks.len()).filter(|i| state.bookmarks[*i].pattern == pattern).collect()
+}
+
+// Saves the current view under the name, replacing a bookmark of the same name.
+pub fn save(state: &mut State, config: &Config, name: &str) {
+    let view = camera::current_view(state, config);
+    let bookmarks = current(state);
+    match bookmarks.iter().find(|i| state.bookmarks[**i].name == name) {
+        Some(i) => state.bookmarks[*i].view = view,
+        None => state.bookmarks.push(Bookmark { pattern: pattern_key(state), name: name.to_string(), view, slot: None })
+    }
+    persist(state, config);
+}
+
+// Index into state.bookmarks of the current pattern's bookmark saved with the number key.
+fn find_slot(state: &State, slot: usize) -> Option<usize> {
+    current(state).into_iter().find(|i| state.bookmarks[*i].slot == Some(slot))
+}
+
+// Saves the current view under the number key, replacing the view saved with it before.
+pub fn save_slot(state: &mut State, config: &Config, slot: usize) {
+    let view = camera::current_view(state, config);
+    match find_slot(state, slot) {
+        Some(i) => state.bookmarks[i].view = view,
+        None => state.bookmarks.push(Bookmark { pattern: pattern_key(state), name: format!("slot {}", slot), view, slot: Some(slot) })
+    }
+    persist(state, config);
+    println!("saved bookmark {}", slot);
+}
+
+pub fn remove(state: &mut State, config: &Config, name: &str) -> Result<(), String> {
+    let i = find(state, name)?;
+    state.bookmarks.remove(i);
+    persist(state, config);
+    Ok(())
+}
+
+// Finds a bookmark of the current pattern by name, or by its number when no name matches.
+pub fn find(state: &State, name: &str) -> Result<usize, String> {
+    let bookmarks = current(state);
+    if let Some(i) = bookmarks.iter().find(|i| state.bookmarks[**i].name == name) { return Ok(*i) }
+    name.parse::<usize>().ok()
+        .and_then(|n| bookmarks.get(n.wrapping_sub(1)).copied())
+        .ok_or(format!("no bookmark {}", name))
+}
+
+pub fn go_to(state: &mut State, config: &Config, i: usize) {
+    state.bookmark_index = current(state).iter().position(|b| *b == i).unwrap_or(0);
+    let view = state.bookmarks[i].view;
+    camera::fly_to(state, config, view);
+}
+
+pub fn go_to_slot(state: &mut State, config: &Config, slot: usize) {
+    match find_slot(state, slot) {
+        Some(i) => go_to(state, config, i),
+        None => println!("no bookmark {}", slot)
+    }
+}
+
+// Flies to the next or previous bookmark of the pattern, wrapping around at the ends.
+pub fn cycle(state: &mut State, config: &Config, offset: i32) {
+    let bookmarks = current(state);
+    if bookmarks.is_empty() { return }
+    let index = (state.bookmark_index as i32 + offset).rem_euclid(bookmarks.len() as i32) as usize;
+    go_to(state, config, bookmarks[index]);
+}
+
+fn persist(state: &State, config: &Config) {
+    if let Err(e) = session::save(config.session_path, &state.bookmarks) { println!("could not save session: {}", e) }
+}
@@ -0,0 +1,174 @@
+use crate::config::Config;
+use crate::coord::Coord;
+use crate::pattern;
+use crate::state::State;
+use crate::state_mgmt;
+use crate::utils;
+
+// Which point of the tracked cells the camera keeps in the middle of the window.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Anchor {
+    Centroid,
+    BoundingBox,
+}
+
+// Region around a moving object, refitted to the object's cells every tick so it moves along with it.
+#[derive(Clone, Copy)]
+pub struct Tracker {
+    pub min: Coord,
+    pub max: Coord,
+    pub anchor: Anchor,
+}
+
+// Animated move of the window center and zoom from one view to another.
+#[derive(Clone, Copy)]
+pub struct Flight {
+    pub from: View,
+    pub to: View,
+    pub progress: f32,
+}
+
+// Cell at the center of the window and the zoom in pixels per cell.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct View {
+    pub x: f32,
+    pub y: f32,
+    pub cell_width: f32,
+}
+
+#[derive(Clone, Copy)]
+pub enum CameraMode {
+    Free,
+    Follow(Tracker),
+    AutoFit,
+    Fly(Flight),
+}
+
+pub fn current_view(state: &State, config: &Config) -> View {
+    View {
+        x: (state.camera_x + config.window_width / 2.0) / state.cell_width,
+        y: (state.camera_y + config.window_height / 2.0) / state.cell_height,
+        cell_width: state.cell_width,
+    }
+}
+
+pub fn fly_to(state: &mut State, config: &Config, to: View) {
+    let from = current_view(state, config);
+    state.camera_mode = CameraMode::Fly(Flight { from, to, progress: 0.0 });
+}
+
+// Starts following the cells of the selection, or stops if already following.
+pub fn toggle_follow(state: &mut State, anchor: Anchor) {
+    if let CameraMode::Follow(tracker) = state.camera_mode {
+        if tracker.anchor == anchor {
+            state.camera_mode = CameraMode::Free;
+            return
+        }
+    }
+
+    let selection = match state.selection { Some(s) => s, None => { println!("select an object to follow"); return } };
+    let cells = state.cell_coords.cells_in_rect(selection.min(), selection.max());
+    match pattern::bounding_box(&cells) {
+        Some((min, max)) => {
+            state.camera_mode = CameraMode::Follow(Tracker { min, max, anchor });
+            println!("following {} cells by {:?}", cells.len(), anchor);
+        },
+        None => println!("nothing to follow in the selection")
+    }
+}
+
+pub fn toggle_auto_fit(state: &mut State) {
+    state.camera_mode = match state.camera_mode {
+        CameraMode::AutoFit => CameraMode::Free,
+        _ => CameraMode::AutoFit
+    };
+}
+
+// Manual panning takes the camera back from follow, auto-fit and flights.
+pub fn stop(state: &mut State) {
+    state.camera_mode = CameraMode::Free;
+}
+
+// Manual zooming only conflicts with the modes that zoom, a followed object can still be zoomed in on.
+pub fn stop_zooming(state: &mut State) {
+    if let CameraMode::AutoFit | CameraMode::Fly(_) = state.camera_mode { state.camera_mode = CameraMode::Free }
+}
+
+// Steers the camera towards its target by setting the same inputs the keys do, so that
+// update_camera accelerates and moves it smoothly.
+pub fn steer(state: &mut State, config: &Config) {
+    let (target_x, target_y) = match state.camera_mode {
+        CameraMode::Free => return,
+        CameraMode::Fly(flight) => return fly(state, config, flight),
+        CameraMode::Follow(tracker) => match track(state, config, tracker) {
+            Some(target) => target,
+            None => {
+                println!("lost the followed object");
+                state.camera_mode = CameraMode::Free;
+                return
+            }
+        },
+        CameraMode::AutoFit => {
+            let (min, max) = match state.cell_coords.bounding_box() { Some(b) => b, None => return };
+            steer_zoom(state, config, min, max);
+            ((min.x + max.x + 1) as f32 / 2.0, (min.y + max.y + 1) as f32 / 2.0)
+        }
+    };
+
+    let dx = target_x * state.cell_width - config.window_width / 2.0 - state.camera_x;
+    let dy = target_y * state.cell_height - config.window_height / 2.0 - state.camera_y;
+    state.camera_x_i = steering_input(dx, config.window_width * config.follow_slowdown);
+    state.camera_y_i = steering_input(dy, config.window_height * config.follow_slowdown);
+}
+
+// Full speed far from the target, slowing down proportionally inside the slowdown distance.
+fn steering_input(distance: f32, slowdown_distance: f32) -> f32 {
+    if distance.abs() < 1.0 { return 0.0 }
+    (distance / slowdown_distance).clamp(-1.0, 1.0)
+}
+
+// Refits the tracker to the cells that are now in or just around it and returns the point to center on.
+fn track(state: &mut State, config: &Config, tracker: Tracker) -> Option<(f32, f32)> {
+    let margin = config.follow_margin;
+    let cells = state.cell_coords.cells_in_rect(
+        Coord::new(tracker.min.x - margin, tracker.min.y - margin),
+        Coord::new(tracker.max.x + margin, tracker.max.y + margin)
+    );
+    let (min, max) = pattern::bounding_box(&cells)?;
+    state.camera_mode = CameraMode::Follow(Tracker { min, max, ..tracker });
+
+    Some(match tracker.anchor {
+        Anchor::Centroid => {
+            let (sum_x, sum_y) = cells.iter().fold((0.0, 0.0), |(x, y), c| (x + c.x as f32, y + c.y as f32));
+            (sum_x / cells.len() as f32 + 0.5, sum_y / cells.len() as f32 + 0.5)
+        },
+        Anchor::BoundingBox => ((min.x + max.x + 1) as f32 / 2.0, (min.y + max.y + 1) as f32 / 2.0)
+    })
+}
+
+// Zooms so that the bounding box fills the window up to the auto-fit margin.
+fn steer_zoom(state: &mut State, config: &Config, min: Coord, max: Coord) {
+    let width = (max.x - min.x + 1) as f32;
+    let height = (max.y - min.y + 1) as f32;
+    let target = ((config.window_width / width).min(config.window_height / height) * config.auto_fit_fill)
+        .min(config.max_cell_width)
+        .max(config.min_cell_width);
+
+    // Compared on a log scale so that zooming in and out by the same factor steer equally hard.
+    let error = (target / state.cell_width).ln();
+    state.camera_z_i = if error.abs() < 0.02 { 0.0 } else { (error * 4.0).clamp(-1.0, 1.0) };
+}
+
+// Eases in and out of the flight, zooming on a log scale so that the zoom changes at an even pace.
+fn fly(state: &mut State, config: &Config, flight: Flight) {
+    let progress = (flight.progress + config.dt / config.flight_duration_ms).min(1.0);
+    let t = progress * progress * (3.0 - 2.0 * progress);
+    let cell_width = utils::lerp(flight.from.cell_width.ln(), flight.to.cell_width.ln(), t).exp()
+        .min(config.max_cell_width)
+        .max(config.min_cell_width);
+
+    state.cell_width = cell_width;
+    state.cell_height = cell_width;
+    state_mgmt::center_on(state, config, utils::lerp(flight.from.x, flight.to.x, t), utils::lerp(flight.from.y, flight.to.y, t));
+    state.camera_mode = if progress < 1.0 { CameraMode::Fly(Flight { progress, ..flight }) } else { CameraMode::Free };
+}
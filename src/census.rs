@@ -0,0 +1,242 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use once_cell::sync::Lazy;
+
+use crate::apgcode;
+use crate::config::Config;
+use crate::coord::Coord;
+use crate::pattern;
+use crate::period;
+use crate::rle;
+use crate::rule::Rule;
+use crate::state::State;
+use crate::universe::Universe;
+
+// Which live cells count as touching when splitting the universe into objects.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Connectivity {
+    // Only cells sharing an edge.
+    Orthogonal,
+    // Cells sharing an edge or a corner.
+    Moore,
+}
+
+#[derive(Clone, Copy)]
+pub struct CensusOptions {
+    pub connectivity: Connectivity,
+    // Keeps unrecognized clusters of objects one dead cell apart together, so that pseudo-objects such as the
+    // bi-block count as one object instead of their parts.
+    pub merge_pseudo_objects: bool,
+}
+
+// Common objects, identified in any phase and orientation. Only valid under B3/S23.
+const KNOWN_OBJECTS: &[(&str, &str)] = &[
+    ("block", "2o$2o!"),
+    ("beehive", "b2o$o2bo$b2o!"),
+    ("loaf", "b2o$o2bo$bobo$2bo!"),
+    ("boat", "2o$obo$bo!"),
+    ("ship", "2o$obo$b2o!"),
+    ("tub", "bo$obo$bo!"),
+    ("pond", "b2o$o2bo$o2bo$b2o!"),
+    ("long boat", "2o$obo$bobo$2bo!"),
+    ("barge", "bo$obo$bobo$2bo!"),
+    ("snake", "2obo$ob2o!"),
+    ("aircraft carrier", "2o$o2bo$2b2o!"),
+    ("mango", "b2o$o2bo$bo2bo$2b2o!"),
+    ("eater 1", "2o$obo$2bo$2b2o!"),
+    ("blinker", "3o!"),
+    ("toad", "b3o$3o!"),
+    ("beacon", "2o$2o$2b2o$2b2o!"),
+    ("pulsar", "2b3o3b3o2$o4bobo4bo$o4bobo4bo$o4bobo4bo$2b3o3b3o2$2b3o3b3o$o4bobo4bo$o4bobo4bo$o4bobo4bo2$2b3o3b3o!"),
+    ("pentadecathlon", "2bo4bo$2ob4ob2o$2bo4bo!"),
+    ("glider", "bo$2bo$3o!"),
+    ("lightweight spaceship", "bo2bo$o$o3bo$4o!"),
+    ("middleweight spaceship", "3bo$bo3bo$o$o4bo$5o!"),
+    ("heavyweight spaceship", "3b2o$bo4bo$o$o5bo$6o!"),
+];
+
+// How far unknown objects are run looking for their period.
+const UNKNOWN_MAX_GENERATIONS: u64 = 100;
+
+// Canonical form of every phase of every known object.
+static CATALOG: Lazy<HashMap<Vec<(i32, i32)>, &'static str>> = Lazy::new(|| {
+    let rule = Rule::life();
+    let mut catalog = HashMap::new();
+    for (name, pattern) in KNOWN_OBJECTS {
+        let cells = rle::parse_pattern(pattern).expect("valid known object");
+        let mut universe: Universe = cells.into_iter().collect();
+        let period = period::detect(&universe, &rule, 30).map(|p| p.period).unwrap_or(1);
+        for _ in 0..period {
+            catalog.insert(canonical(&universe.iter().collect::<Vec<_>>()), *name);
+            universe = universe.step(&rule);
+        }
+    }
+    catalog
+});
+
+// Splits the cells into groups of cells that are at most `reach` cells apart.
+fn components(cells: &[Coord], connectivity: Connectivity, reach: i32) -> Vec<Vec<Coord>> {
+    let mut offsets = Vec::new();
+    for dy in -reach..=reach {
+        for dx in -reach..=reach {
+            if (dx, dy) == (0, 0) { continue }
+            if connectivity == Connectivity::Orthogonal && dx != 0 && dy != 0 { continue }
+            offsets.push((dx, dy));
+        }
+    }
+
+    let mut remaining: HashSet<Coord> = cells.iter().copied().collect();
+    let mut components = Vec::new();
+    while let Some(start) = remaining.iter().next().copied() {
+        remaining.remove(&start);
+        let mut component = vec![start];
+        let mut queue = VecDeque::from([start]);
+        while let Some(coord) = queue.pop_front() {
+            for (dx, dy) in &offsets {
+                let neighbor = Coord::new(coord.x + dx, coord.y + dy);
+                if remaining.remove(&neighbor) {
+                    component.push(neighbor);
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+        components.push(component);
+    }
+    components
+}
+
+// Splits the universe into objects. Cells one dead cell apart are grouped first, so that objects with gaps
+// such as the pulsar are recognized, unrecognized groups are split along the connectivity unless merged.
+pub fn objects(universe: &Universe, rule: &Rule, options: &CensusOptions) -> Vec<Vec<Coord>> {
+    let cells: Vec<Coord> = universe.iter().collect();
+    let mut objects = Vec::new();
+    for group in components(&cells, Connectivity::Moore, 2) {
+        if options.merge_pseudo_objects || known_name(&group, rule).is_some() {
+            objects.push(group);
+        } else {
+            objects.extend(components(&group, options.connectivity, 1));
+        }
+    }
+    objects
+}
+
+// The smallest of the eight rotations and reflections, moved to (0, 0), so that all orientations compare equal.
+pub fn canonical(cells: &[Coord]) -> Vec<(i32, i32)> {
+    let mut orientation = cells.to_vec();
+    let mut best: Option<Vec<(i32, i32)>> = None;
+    for i in 0..8 {
+        if i == 4 { orientation = pattern::flip_horizontal(&orientation) }
+        let mut key: Vec<(i32, i32)> = pattern::normalize(&orientation).iter().map(|c| (c.y, c.x)).collect();
+        key.sort_unstable();
+        if best.as_ref().is_none_or(|b| key < *b) { best = Some(key) }
+        orientation = pattern::rotate_cw(&orientation);
+    }
+    best.unwrap_or_default()
+}
+
+pub fn known_name(cells: &[Coord], rule: &Rule) -> Option<&'static str> {
+    if *rule != Rule::life() { return None }
+    CATALOG.get(&canonical(cells)).copied()
+}
+
+// Name of a known object, the apgcode of other periodic ones, or the RLE of anything else.
+pub fn identify(cells: &[Coord], rule: &Rule) -> String {
+    if let Some(name) = known_name(cells, rule) { return name.to_string() }
+    let universe: Universe = cells.iter().copied().collect();
+    if period::detect(&universe, rule, UNKNOWN_MAX_GENERATIONS).is_some_and(|p| p.start == 0) {
+        if let Ok(code) = apgcode::encode(cells, rule, UNKNOWN_MAX_GENERATIONS) { return code }
+    }
+    let coords: Vec<Coord> = canonical(cells).iter().map(|(y, x)| Coord::new(*x, *y)).collect();
+    let encoded = rle::encode_pattern(&coords, rule);
+    let body: String = encoded.lines().skip(1).collect();
+    format!("unknown {}-cell object {}", cells.len(), body)
+}
+
+// Number of objects of each kind, most common first.
+pub fn census(universe: &Universe, rule: &Rule, options: &CensusOptions) -> Vec<(String, usize)> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for object in objects(universe, rule, options) {
+        *counts.entry(identify(&object, rule)).or_insert(0) += 1;
+    }
+    let mut counts: Vec<(String, usize)> = counts.into_iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts
+}
+
+// Census of the selection, or of the whole universe without one.
+pub fn census_of_state(state: &State, config: &Config) -> Vec<(String, usize)> {
+    match state.selection {
+        Some(s) => {
+            let universe: Universe = state.cell_coords.cells_in_rect(s.min(), s.max()).into_iter().collect();
+            census(&universe, &state.rule, &config.census_options)
+        },
+        None => census(&state.cell_coords, &state.rule, &config.census_options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn universe(pattern: &str) -> Universe {
+        rle::parse_pattern(pattern).unwrap().into_iter().collect()
+    }
+
+    fn options(connectivity: Connectivity, merge_pseudo_objects: bool) -> CensusOptions {
+        CensusOptions { connectivity, merge_pseudo_objects }
+    }
+
+    #[test]
+    fn identifies_known_objects_in_every_phase_and_orientation() {
+        let rule = Rule::life();
+        for (name, pattern) in KNOWN_OBJECTS {
+            let mut phase = universe(pattern);
+            let period = period::detect(&phase, &rule, 30).unwrap().period;
+            for _ in 0..period {
+                let mut orientation: Vec<Coord> = phase.iter().map(|c| Coord::new(c.x + 7, c.y - 3)).collect();
+                for i in 0..8 {
+                    if i == 4 { orientation = pattern::flip_vertical(&orientation) }
+                    assert_eq!(known_name(&orientation, &rule), Some(*name));
+                    orientation = pattern::rotate_cw(&orientation);
+                }
+                phase = phase.step(&rule);
+            }
+        }
+        assert_eq!(known_name(&universe("2o$2o!").iter().collect::<Vec<_>>(), &Rule::parse("B36/S23").unwrap()), None);
+    }
+
+    #[test]
+    fn splits_pseudo_objects_unless_merged() {
+        let bi_block = universe("2ob2o$2ob2o!");
+        let rule = Rule::life();
+        assert_eq!(census(&bi_block, &rule, &options(Connectivity::Moore, false)), vec![("block".to_string(), 2)]);
+        let merged = objects(&bi_block, &rule, &options(Connectivity::Moore, true));
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].len(), 8);
+    }
+
+    #[test]
+    fn keeps_objects_with_gaps_together() {
+        let pulsar = universe(KNOWN_OBJECTS.iter().find(|(name, _)| *name == "pulsar").unwrap().1);
+        for connectivity in [Connectivity::Orthogonal, Connectivity::Moore] {
+            assert_eq!(census(&pulsar, &Rule::life(), &options(connectivity, false)), vec![("pulsar".to_string(), 1)]);
+        }
+    }
+
+    #[test]
+    fn splits_diagonal_neighbors_by_connectivity() {
+        let pair = universe("o$bo!");
+        assert_eq!(objects(&pair, &Rule::life(), &options(Connectivity::Orthogonal, false)).len(), 2);
+        assert_eq!(objects(&pair, &Rule::life(), &options(Connectivity::Moore, false)).len(), 1);
+    }
+
+    #[test]
+    fn falls_back_to_the_rle_of_objects_that_are_not_periodic() {
+        let pre_block: Vec<Coord> = universe("2o$bo!").iter().collect();
+        assert_eq!(identify(&pre_block, &Rule::life()), "unknown 3-cell object 2o$o!");
+        let r_pentomino: Vec<Coord> = universe("b2o$2o$bo!").iter().collect();
+        assert!(identify(&r_pentomino, &Rule::life()).starts_with("unknown 5-cell object "));
+        let beehive_pair: Vec<Coord> = universe("b2o4b2o$o2bo2bo2bo$b2o4b2o!").iter().collect();
+        assert!(identify(&beehive_pair, &Rule::life()).starts_with("xs12_"));
+    }
+}
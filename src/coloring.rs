@@ -0,0 +1,69 @@
+use sdl2::pixels::Color;
+
+use crate::config::Config;
+use crate::coord::Coord;
+use crate::state::State;
+use crate::utils;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum RenderMode {
+    Plain,
+    Age,
+    Activity,
+    Heat,
+}
+
+impl RenderMode {
+    pub fn next(self) -> RenderMode {
+        match self {
+            RenderMode::Plain => RenderMode::Age,
+            RenderMode::Age => RenderMode::Activity,
+            RenderMode::Activity => RenderMode::Heat,
+            RenderMode::Heat => RenderMode::Plain,
+        }
+    }
+
+    pub fn tracks_births(self) -> bool {
+        matches!(self, RenderMode::Age | RenderMode::Activity)
+    }
+
+    pub fn tracks_heat(self) -> bool {
+        self == RenderMode::Heat
+    }
+}
+
+pub fn cell_color(state: &State, config: &Config, coord: &Coord) -> Color {
+    match state.render_mode {
+        RenderMode::Age => {
+            let t = (state.age(coord) as f32 / config.age_gradient_generations as f32).min(1.0);
+            lerp_color(config.young_cell_color, config.old_cell_color, t)
+        },
+        RenderMode::Activity => {
+            if state.age(coord) == 0 {
+                config.newborn_cell_color
+            } else if !state.should_live(coord) {
+                config.dying_cell_color
+            } else {
+                config.cell_color
+            }
+        },
+        RenderMode::Plain | RenderMode::Heat => config.cell_color,
+    }
+}
+
+// Color of a location that changed state `heat` times during the last `config.heat_generations` generations.
+pub fn heat_color(config: &Config, heat: u32) -> Color {
+    let t = (heat as f32 / config.heat_generations as f32).min(1.0);
+    let mut color = config.heat_color;
+    color.a = (utils::lerp(0.1, 1.0, t) * 255.0) as u8;
+    color
+}
+
+fn lerp_color(start: Color, end: Color, t: f32) -> Color {
+    Color::RGBA(
+        utils::lerp(start.r as f32, end.r as f32, t) as u8,
+        utils::lerp(start.g as f32, end.g as f32, t) as u8,
+        utils::lerp(start.b as f32, end.b as f32, t) as u8,
+        utils::lerp(start.a as f32, end.a as f32, t) as u8,
+    )
+}
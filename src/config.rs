@@ -1,16 +1,24 @@
 use sdl2::pixels::Color;
 
+use crate::bindings::Bindings;
+use crate::census::{CensusOptions, Connectivity};
+use crate::predecessor::PredecessorOptions;
+use crate::rule::Rule;
+use crate::soup::SoupOptions;
+
 const WINDOW_WIDTH: f32 = 800.0;
 const WINDOW_HEIGHT: f32 = 600.0;
 const MS_PER_UPDATE: f32 = 16.0;
-const MIN_CELL_WIDTH: f32 = 1.0;
-const MIN_CELL_HEIGHT: f32 = 1.0;
+const MIN_CELL_WIDTH: f32 = 1.0 / 64.0;
+const MIN_CELL_HEIGHT: f32 = 1.0 / 64.0;
 const MAX_CELL_WIDTH: f32 = 100.0;
 const MAX_CELL_HEIGHT: f32 = 100.0;
 const INTRO_DURATION_MS: f32 = 0.0;
 const FONT_PATH: &'static str = "./ARCADECLASSIC.TTF";
 const CHAR_WIDTH: f32 = 30.0;
 const CHAR_HEIGHT: f32 = 50.0;
+const SMALL_CHAR_WIDTH: f32 = 9.0;
+const SMALL_CHAR_HEIGHT: f32 = 16.0;
 const BACKGROUND_COLOR: Color = Color::WHITE;
 const GRID_COLOR: Color = Color::BLACK;
 const CELL_COLOR: Color = Color::BLACK;
@@ -22,6 +30,74 @@ const CAMERA_XY_VELOCITY_MAX: f32 = 50.0;
 const CAMERA_Z_ACCELERATION: f32 = 0.001;
 const CAMERA_Z_ACCELERATION_MAX: f32 = 0.01;
 const CAMERA_Z_VELOCITY_MAX: f32 = 0.1;
+const YOUNG_CELL_COLOR: Color = Color::RGBA(255, 140, 0, 255);
+const OLD_CELL_COLOR: Color = Color::RGBA(40, 0, 120, 255);
+const AGE_GRADIENT_GENERATIONS: u32 = 100;
+const NEWBORN_CELL_COLOR: Color = Color::RGBA(0, 180, 0, 255);
+const DYING_CELL_COLOR: Color = Color::RGBA(220, 0, 0, 255);
+const HEAT_COLOR: Color = Color::RGBA(255, 60, 0, 255);
+const HEAT_GENERATIONS: u32 = 32;
+const HISTORY_COLOR: Color = Color::RGBA(0, 150, 255, 60);
+const HISTORY_PATH: &str = "./history.rle";
+const MINIMAP_WIDTH: f32 = 160.0;
+const MINIMAP_HEIGHT: f32 = 120.0;
+const MINIMAP_MARGIN: f32 = 10.0;
+const MINIMAP_BACKGROUND_COLOR: Color = Color::RGBA(255, 255, 255, 220);
+const MINIMAP_BORDER_COLOR: Color = Color::GREY;
+const MINIMAP_VIEWPORT_COLOR: Color = Color::RGBA(255, 0, 0, 200);
+const GRAPH_WIDTH: f32 = 240.0;
+const GRAPH_HEIGHT: f32 = 100.0;
+const GRAPH_MARGIN: f32 = 10.0;
+const GRAPH_BACKGROUND_COLOR: Color = Color::RGBA(255, 255, 255, 220);
+const GRAPH_POPULATION_COLOR: Color = Color::RGBA(0, 0, 0, 255);
+const GRAPH_BIRTHS_COLOR: Color = Color::RGBA(0, 160, 0, 255);
+const GRAPH_DEATHS_COLOR: Color = Color::RGBA(200, 0, 0, 255);
+const POPULATION_CSV_PATH: &str = "./population.csv";
+// Generations remembered for period detection, longer periods go unnoticed.
+const PERIOD_HISTORY: usize = 4096;
+const EMISSION_DELETE_DISTANCE: Option<i32> = None;
+const EMISSION_CHECK_INTERVAL: u64 = 1;
+const PREDECESSOR_MARGIN: i32 = 1;
+const PREDECESSOR_EXACT: bool = false;
+const PREDECESSOR_MAX_CONFLICTS: u64 = 200_000;
+const SEARCH_MAX_RESULTS: usize = 100;
+const SEARCH_MAX_CONFLICTS: u64 = 1_000_000;
+const SEARCH_DIR: &str = "./search";
+const BINDINGS_PATH: &str = "./bindings.txt";
+const HELP_BACKGROUND_COLOR: Color = Color::RGBA(255, 255, 255, 230);
+const CONSOLE_BACKGROUND_COLOR: Color = Color::RGBA(255, 255, 255, 230);
+const CONSOLE_LINES: usize = 12;
+const PATTERN_DIR: &str = "./patterns";
+const LIBRARY_PATH: &str = "./library.txt";
+const LIBRARY_WIDTH: f32 = 320.0;
+const LIBRARY_PREVIEW_INTERVAL: f32 = 100.0;
+const LIBRARY_BACKGROUND_COLOR: Color = Color::RGBA(255, 255, 255, 230);
+const LIBRARY_HIGHLIGHT_COLOR: Color = Color::RGBA(0, 120, 255, 80);
+const DENSE_TILE_POPULATION: u32 = 64;
+const DENSITY_MIN_ALPHA: f32 = 0.2;
+const WHEEL_ZOOM_FACTOR: f32 = 1.1;
+const PINCH_ZOOM_SENSITIVITY: f32 = 4.0;
+const PAN_FRICTION: f32 = 0.9;
+const PAN_FLING_WINDOW_MS: u32 = 50;
+const FOLLOW_MARGIN: i32 = 2;
+const FOLLOW_SLOWDOWN: f32 = 0.25;
+const AUTO_FIT_FILL: f32 = 0.9;
+const FLIGHT_DURATION_MS: f32 = 800.0;
+const SESSION_PATH: &str = "./session.txt";
+const CENSUS_CONNECTIVITY: Connectivity = Connectivity::Moore;
+const CENSUS_MERGE_PSEUDO_OBJECTS: bool = false;
+const APGCODE_MAX_GENERATIONS: u64 = 1000;
+const SOUP_SIZE: i32 = 16;
+const SOUP_DENSITY: f64 = 0.5;
+const SOUP_MAX_GENERATIONS: u64 = 10000;
+const SOUP_RARE_THRESHOLD: u64 = 2;
+const SOUP_DIR: &str = "./soups";
+const SVG_PATH: &str = "./viewport.svg";
+const SVG_GRID: bool = true;
+const SELECTION_COLOR: Color = Color::RGBA(0, 120, 255, 255);
+const SELECTION_PATH: &str = "./selection.rle";
+const RANDOM_FILL_DENSITY: f32 = 0.5;
+const STAMP_COLOR: Color = Color::RGBA(0, 0, 0, 96);
 
 pub struct Config {
     pub window_width: f32,
@@ -35,6 +111,8 @@ pub struct Config {
     pub font_path: &'static str,
     pub char_width: f32,
     pub char_height: f32,
+    pub small_char_width: f32,
+    pub small_char_height: f32,
     pub background_color: Color,
     pub grid_color: Color,
     pub cell_color: Color,
@@ -46,6 +124,74 @@ pub struct Config {
     pub camera_z_acceleration: f32,
     pub camera_z_acceleration_max: f32,
     pub camera_z_velocity_max: f32,
+    pub young_cell_color: Color,
+    pub old_cell_color: Color,
+    pub age_gradient_generations: u32,
+    pub newborn_cell_color: Color,
+    pub dying_cell_color: Color,
+    pub heat_color: Color,
+    pub heat_generations: u32,
+    pub history_color: Color,
+    pub history_path: &'static str,
+    pub minimap_width: f32,
+    pub minimap_height: f32,
+    pub minimap_margin: f32,
+    pub minimap_background_color: Color,
+    pub minimap_border_color: Color,
+    pub minimap_viewport_color: Color,
+    pub graph_width: f32,
+    pub graph_height: f32,
+    pub graph_margin: f32,
+    pub graph_background_color: Color,
+    pub graph_population_color: Color,
+    pub graph_births_color: Color,
+    pub graph_deaths_color: Color,
+    pub population_csv_path: &'static str,
+    pub period_history: usize,
+    // Escaping spaceships further than this from everything else are deleted while emissions are tracked.
+    pub emission_delete_distance: Option<i32>,
+    pub emission_check_interval: u64,
+    pub predecessor_options: PredecessorOptions,
+    // Still life and oscillator searches stop after this many patterns, which are saved to search_dir.
+    pub search_max_results: usize,
+    pub search_max_conflicts: u64,
+    pub search_dir: &'static str,
+    pub bindings_path: &'static str,
+    pub bindings: Bindings,
+    pub help_background_color: Color,
+    pub console_background_color: Color,
+    pub console_lines: usize,
+    pub pattern_dirs: Vec<String>,
+    pub library_path: &'static str,
+    pub library_width: f32,
+    pub library_preview_interval: f32,
+    pub library_background_color: Color,
+    pub library_highlight_color: Color,
+    pub dense_tile_population: u32,
+    pub density_min_alpha: f32,
+    pub wheel_zoom_factor: f32,
+    pub pinch_zoom_sensitivity: f32,
+    pub pan_friction: f32,
+    // Releasing a drag keeps the camera moving at the speed of the last milliseconds of the drag, if it moved then.
+    pub pan_fling_window_ms: u32,
+    pub follow_margin: i32,
+    pub follow_slowdown: f32,
+    pub auto_fit_fill: f32,
+    pub flight_duration_ms: f32,
+    pub session_path: &'static str,
+    pub census_options: CensusOptions,
+    pub apgcode_max_generations: u64,
+    pub soup_options: SoupOptions,
+    // Objects found at most this many times in a search are rare, their soups are saved to soup_dir.
+    pub soup_rare_threshold: u64,
+    pub soup_dir: &'static str,
+    pub svg_path: &'static str,
+    pub svg_grid: bool,
+    pub selection_color: Color,
+    pub selection_path: &'static str,
+    pub stamp_color: Color,
+    pub random_fill_density: f32,
+    pub pixel_density: f32,
     pub font: Option<sdl2::ttf::Font<'static, 'static>>,
 }
 
@@ -63,6 +209,8 @@ impl Config {
             font_path: FONT_PATH,
             char_width: CHAR_WIDTH,
             char_height: CHAR_HEIGHT,
+            small_char_width: SMALL_CHAR_WIDTH,
+            small_char_height: SMALL_CHAR_HEIGHT,
             background_color: BACKGROUND_COLOR,
             grid_color: GRID_COLOR,
             cell_color: CELL_COLOR,
@@ -74,7 +222,97 @@ impl Config {
             camera_z_acceleration: CAMERA_Z_ACCELERATION,
             camera_z_acceleration_max: CAMERA_Z_ACCELERATION_MAX,
             camera_z_velocity_max: CAMERA_Z_VELOCITY_MAX,
+            young_cell_color: YOUNG_CELL_COLOR,
+            old_cell_color: OLD_CELL_COLOR,
+            age_gradient_generations: AGE_GRADIENT_GENERATIONS,
+            newborn_cell_color: NEWBORN_CELL_COLOR,
+            dying_cell_color: DYING_CELL_COLOR,
+            heat_color: HEAT_COLOR,
+            heat_generations: HEAT_GENERATIONS,
+            history_color: HISTORY_COLOR,
+            history_path: HISTORY_PATH,
+            minimap_width: MINIMAP_WIDTH,
+            minimap_height: MINIMAP_HEIGHT,
+            minimap_margin: MINIMAP_MARGIN,
+            minimap_background_color: MINIMAP_BACKGROUND_COLOR,
+            minimap_border_color: MINIMAP_BORDER_COLOR,
+            minimap_viewport_color: MINIMAP_VIEWPORT_COLOR,
+            graph_width: GRAPH_WIDTH,
+            graph_height: GRAPH_HEIGHT,
+            graph_margin: GRAPH_MARGIN,
+            graph_background_color: GRAPH_BACKGROUND_COLOR,
+            graph_population_color: GRAPH_POPULATION_COLOR,
+            graph_births_color: GRAPH_BIRTHS_COLOR,
+            graph_deaths_color: GRAPH_DEATHS_COLOR,
+            population_csv_path: POPULATION_CSV_PATH,
+            period_history: PERIOD_HISTORY,
+            emission_delete_distance: EMISSION_DELETE_DISTANCE,
+            emission_check_interval: EMISSION_CHECK_INTERVAL,
+            predecessor_options: PredecessorOptions {
+                margin: PREDECESSOR_MARGIN,
+                exact: PREDECESSOR_EXACT,
+                max_conflicts: PREDECESSOR_MAX_CONFLICTS,
+            },
+            search_max_results: SEARCH_MAX_RESULTS,
+            search_max_conflicts: SEARCH_MAX_CONFLICTS,
+            search_dir: SEARCH_DIR,
+            bindings_path: BINDINGS_PATH,
+            bindings: Bindings::new(),
+            help_background_color: HELP_BACKGROUND_COLOR,
+            console_background_color: CONSOLE_BACKGROUND_COLOR,
+            console_lines: CONSOLE_LINES,
+            pattern_dirs: vec![PATTERN_DIR.to_string()],
+            library_path: LIBRARY_PATH,
+            library_width: LIBRARY_WIDTH,
+            library_preview_interval: LIBRARY_PREVIEW_INTERVAL,
+            library_background_color: LIBRARY_BACKGROUND_COLOR,
+            library_highlight_color: LIBRARY_HIGHLIGHT_COLOR,
+            dense_tile_population: DENSE_TILE_POPULATION,
+            density_min_alpha: DENSITY_MIN_ALPHA,
+            wheel_zoom_factor: WHEEL_ZOOM_FACTOR,
+            pinch_zoom_sensitivity: PINCH_ZOOM_SENSITIVITY,
+            pan_friction: PAN_FRICTION,
+            pan_fling_window_ms: PAN_FLING_WINDOW_MS,
+            follow_margin: FOLLOW_MARGIN,
+            follow_slowdown: FOLLOW_SLOWDOWN,
+            auto_fit_fill: AUTO_FIT_FILL,
+            flight_duration_ms: FLIGHT_DURATION_MS,
+            session_path: SESSION_PATH,
+            census_options: CensusOptions { connectivity: CENSUS_CONNECTIVITY, merge_pseudo_objects: CENSUS_MERGE_PSEUDO_OBJECTS },
+            apgcode_max_generations: APGCODE_MAX_GENERATIONS,
+            soup_options: SoupOptions {
+                size: SOUP_SIZE,
+                density: SOUP_DENSITY,
+                max_generations: SOUP_MAX_GENERATIONS,
+                rule: Rule::life(),
+                census: CensusOptions { connectivity: CENSUS_CONNECTIVITY, merge_pseudo_objects: CENSUS_MERGE_PSEUDO_OBJECTS },
+            },
+            soup_rare_threshold: SOUP_RARE_THRESHOLD,
+            soup_dir: SOUP_DIR,
+            svg_path: SVG_PATH,
+            svg_grid: SVG_GRID,
+            selection_color: SELECTION_COLOR,
+            selection_path: SELECTION_PATH,
+            stamp_color: STAMP_COLOR,
+            random_fill_density: RANDOM_FILL_DENSITY,
+            pixel_density: 1.0,
             font: None,
         }
     }
+
+    // Scales sizes given in window points to drawable pixels, which differ on HiDPI displays.
+    pub fn set_pixel_density(&mut self, pixel_density: f32) {
+        self.pixel_density = pixel_density;
+        self.char_width = CHAR_WIDTH * pixel_density;
+        self.char_height = CHAR_HEIGHT * pixel_density;
+        self.small_char_width = SMALL_CHAR_WIDTH * pixel_density;
+        self.small_char_height = SMALL_CHAR_HEIGHT * pixel_density;
+        self.minimap_width = MINIMAP_WIDTH * pixel_density;
+        self.minimap_height = MINIMAP_HEIGHT * pixel_density;
+        self.minimap_margin = MINIMAP_MARGIN * pixel_density;
+        self.graph_width = GRAPH_WIDTH * pixel_density;
+        self.graph_height = GRAPH_HEIGHT * pixel_density;
+        self.graph_margin = GRAPH_MARGIN * pixel_density;
+        self.library_width = LIBRARY_WIDTH * pixel_density;
+    }
 }
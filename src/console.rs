@@ -0,0 +1,351 @@
+use std::fs;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+use crate::apgcode;
+use crate::bookmarks;
+use crate::camera::{self, View};
+use crate::census;
+use crate::config::Config;
+use crate::library;
+use crate::population;
+use crate::predecessor;
+use crate::search::{self, SearchOptions, Symmetry};
+use crate::rle;
+use crate::rule::Rule;
+use crate::selection;
+use crate::soup;
+use crate::stamp;
+use crate::state::State;
+use crate::state_mgmt;
+use crate::utils;
+
+const COMMANDS: &[(&str, &str)] = &[
+    ("load", "load <file>"),
+    ("save", "save <file>"),
+    ("stamp", "stamp <file>"),
+    ("rule", "rule [B3/S23]"),
+    ("goto", "goto <x> <y>"),
+    ("bookmark", "bookmark [name]"),
+    ("jump", "jump <bookmark>"),
+    ("unbookmark", "unbookmark <bookmark>"),
+    ("step", "step [generations]"),
+    ("speed", "speed <generations per second>"),
+    ("zoom", "zoom <pixels per cell>"),
+    ("clear", "clear"),
+    ("random", "random <density>"),
+    ("census", "census"),
+    ("apgcode", "apgcode [code]"),
+    ("soup", "soup <seed>"),
+    ("population", "population [file]"),
+    ("emissions", "emissions"),
+    ("predecessor", "predecessor"),
+    ("search", "search <width> <height> [period] [symmetry]"),
+    ("help", "help"),
+];
+const MAX_OUTPUT_LINES: usize = 100;
+
+pub struct Console {
+    pub open: bool,
+    pub input: String,
+    pub output: Vec<String>,
+    history: Vec<String>,
+    history_index: Option<usize>,
+    // Progress of a running search, which runs on its own thread so that the window keeps responding.
+    search: Option<Receiver<String>>,
+}
+
+impl Console {
+    pub fn new() -> Console {
+        Console {
+            open: false,
+            input: String::new(),
+            output: Vec::new(),
+            history: Vec::new(),
+            history_index: None,
+            search: None,
+        }
+    }
+
+    // Prints the progress the running search has made since the last frame.
+    pub fn update(&mut self) {
+        let receiver = match &self.search { Some(r) => r, None => return };
+        let mut lines = Vec::new();
+        loop {
+            match receiver.try_recv() {
+                Ok(line) => lines.push(line),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => { self.search = None; break }
+            }
+        }
+        for line in lines { self.print(line) }
+    }
+
+    pub fn print(&mut self, line: String) {
+        self.output.push(line);
+        if self.output.len() > MAX_OUTPUT_LINES { self.output.remove(0); }
+    }
+
+    pub fn history_previous(&mut self) {
+        if self.history.is_empty() { return }
+        let index = match self.history_index {
+            Some(i) => i.saturating_sub(1),
+            None => self.history.len() - 1
+        };
+        self.history_index = Some(index);
+        self.input = self.history[index].clone();
+    }
+
+    pub fn history_next(&mut self) {
+        let index = match self.history_index { Some(i) => i + 1, None => return };
+        if index < self.history.len() {
+            self.history_index = Some(index);
+            self.input = self.history[index].clone();
+        } else {
+            self.history_index = None;
+            self.input.clear();
+        }
+    }
+
+    // Completes command names for the first word and file paths for the rest.
+    pub fn complete(&mut self) {
+        let (head, last) = match self.input.rfind(' ') {
+            Some(i) => self.input.split_at(i + 1),
+            None => ("", self.input.as_str())
+        };
+        let candidates: Vec<String> = if head.is_empty() {
+            COMMANDS.iter().filter(|(name, _)| name.starts_with(last)).map(|(name, _)| format!("{} ", name)).collect()
+        } else {
+            path_candidates(last)
+        };
+
+        match candidates.len() {
+            0 => {},
+            1 => self.input = format!("{}{}", head, candidates[0]),
+            _ => {
+                self.input = format!("{}{}", head, common_prefix(&candidates));
+                let listing = candidates.join("  ");
+                self.print(listing);
+            }
+        }
+    }
+}
+
+impl Default for Console {
+    fn default() -> Console {
+        Console::new()
+    }
+}
+
+// Runs the current input line and shows its result or error below it.
+pub fn submit(state: &mut State, config: &Config) {
+    let line = std::mem::take(&mut state.console.input);
+    state.console.history_index = None;
+    if line.trim().is_empty() { return }
+    if state.console.history.last() != Some(&line) { state.console.history.push(line.clone()) }
+
+    state.console.print(format!("> {}", line));
+    let result = execute(state, config, &line);
+    match result {
+        Ok(message) if message.is_empty() => {},
+        Ok(message) => {
+            for line in message.lines() { state.console.print(line.to_string()) }
+        },
+        Err(e) => state.console.print(format!("error: {}", e)),
+    }
+}
+
+pub fn execute(state: &mut State, config: &Config, line: &str) -> Result<String, String> {
+    let args: Vec<&str> = line.split_whitespace().collect();
+    let (command, args) = match args.split_first() { Some(a) => a, None => return Ok(String::new()) };
+
+    match *command {
+        "load" => {
+            let path = single_arg(args, "load <file>")?;
+            library::load(state, config, path)?;
+            Ok(format!("loaded {}: {} cells, rule {}", path, state.cell_coords.len(), state.rule))
+        },
+        "save" => {
+            let path = single_arg(args, "save <file>")?;
+            let cells: Vec<_> = state.cell_coords.iter().collect();
+            rle::save_pattern(path, &cells, &state.rule)?;
+            Ok(format!("saved {}: {} cells", path, cells.len()))
+        },
+        "stamp" => {
+            let path = single_arg(args, "stamp <file>")?;
+            stamp::pick_file(state, path)?;
+            Ok(format!("stamping {}, click to place", path))
+        },
+        "rule" => {
+            if let Some(rule) = args.first() { state.set_rule(Rule::parse(rule)?) }
+            Ok(format!("rule {}", state.rule))
+        },
+        "goto" => {
+            let (x, y) = match args {
+                [x, y] => (parse_number::<i32>(x)?, parse_number::<i32>(y)?),
+                _ => return Err("usage: goto <x> <y>".to_string())
+            };
+            let cell_width = state.cell_width;
+            camera::fly_to(state, config, View { x: x as f32 + 0.5, y: y as f32 + 0.5, cell_width });
+            Ok(String::new())
+        },
+        "bookmark" => {
+            if args.is_empty() {
+                let names: Vec<String> = bookmarks::current(state).iter().enumerate()
+                    .map(|(n, i)| format!("{} {}", n + 1, state.bookmarks[*i].name))
+                    .collect();
+                return Ok(if names.is_empty() { "no bookmarks".to_string() } else { names.join(", ") })
+            }
+            let name = args.join(" ");
+            bookmarks::save(state, config, &name);
+            Ok(format!("saved bookmark {}", name))
+        },
+        "jump" => {
+            let i = bookmarks::find(state, &args.join(" "))?;
+            bookmarks::go_to(state, config, i);
+            Ok(String::new())
+        },
+        "unbookmark" => {
+            let name = args.join(" ");
+            bookmarks::remove(state, config, &name)?;
+            Ok(format!("removed bookmark {}", name))
+        },
+        "step" => {
+            let generations = match args.first() { Some(n) => parse_number::<u32>(n)?, None => 1 };
+            for _ in 0..generations {
+                state_mgmt::step(state, config);
+            }
+            Ok(format!("generation {}, population {}", state.generation, state.cell_coords.len()))
+        },
+        "speed" => {
+            let speed = parse_number::<f32>(single_arg(args, "speed <generations per second>")?)?;
+            if speed <= 0.0 { return Err("speed must be positive".to_string()) }
+            state.cell_update_interval = (1000.0 / speed).max(config.dt).min(5000.0);
+            Ok(format!("speed {} generations per second", 1000.0 / state.cell_update_interval))
+        },
+        "zoom" => {
+            let cell_width = parse_number::<f32>(single_arg(args, "zoom <pixels per cell>")?)?;
+            if cell_width <= 0.0 { return Err("zoom must be positive".to_string()) }
+            let factor = cell_width / state.cell_width;
+            state_mgmt::zoom_at(state, config, factor, config.window_width / 2.0, config.window_height / 2.0);
+            Ok(format!("zoom {} pixels per cell", state.cell_width))
+        },
+        "clear" => {
+            state.reset_cell_coords();
+            Ok(String::new())
+        },
+        "random" => {
+            let density = parse_number::<f32>(single_arg(args, "random <density>")?)?;
+            if !(0.0..=1.0).contains(&density) { return Err("density must be between 0 and 1".to_string()) }
+            let (min, max) = match state.selection {
+                Some(s) => (s.min(), s.max()),
+                None => utils::visible_cell_bounds(state, config)
+            };
+            selection::random_fill_rect(state, min, max, density);
+            Ok(String::new())
+        },
+        "census" => {
+            let counts = census::census_of_state(state, config);
+            if counts.is_empty() { return Ok("no objects".to_string()) }
+            Ok(counts.iter().map(|(name, count)| format!("{} {}", count, name)).collect::<Vec<_>>().join("\n"))
+        },
+        "apgcode" => match args {
+            // Without a code, encodes the selection or the whole universe.
+            [] => {
+                let cells = if state.selection.is_some() { selection::selected_cells(state) } else { state.cell_coords.iter().collect() };
+                apgcode::encode(&cells, &state.rule, config.apgcode_max_generations)
+            },
+            [code] => {
+                let cells = apgcode::decode(code)?;
+                stamp::pick(state, &cells);
+                Ok(format!("stamping {}, click to place", code))
+            },
+            _ => Err("usage: apgcode [code]".to_string())
+        },
+        "soup" => {
+            let seed = parse_number::<u64>(single_arg(args, "soup <seed>")?)?;
+            let options = &config.soup_options;
+            stamp::pick(state, &soup::soup(seed, options.size, options.density));
+            Ok(format!("stamping soup {}, click to place", seed))
+        },
+        "population" => {
+            let path = args.first().copied().unwrap_or(config.population_csv_path);
+            population::save_csv(path, &state.population_history)?;
+            Ok(format!("saved {} generations to {}", state.population_history.samples.len(), path))
+        },
+        "emissions" => match &state.emission_tracker {
+            Some(tracker) => Ok(tracker.describe()),
+            None => Err("emission tracking is off".to_string())
+        },
+        "predecessor" => predecessor::find_for_selection(state, config),
+        "search" => {
+            let (width, height, period, symmetry) = match args {
+                [width, height] => (width, height, "1", "C1"),
+                [width, height, period] => (width, height, *period, "C1"),
+                [width, height, period, symmetry] => (width, height, *period, *symmetry),
+                _ => return Err("usage: search <width> <height> [period] [symmetry]".to_string())
+            };
+            let options = SearchOptions {
+                width: parse_number::<i32>(width)?,
+                height: parse_number::<i32>(height)?,
+                period: parse_number::<u64>(period)?,
+                symmetry: Symmetry::parse(symmetry)?,
+                rule: state.rule,
+                max_conflicts: config.search_max_conflicts,
+            };
+            if state.console.search.is_some() { return Err("a search is already running".to_string()) }
+            let (max_results, dir) = (config.search_max_results, config.search_dir);
+            let (sender, receiver) = mpsc::channel();
+            thread::spawn(move || {
+                let result = search::run(&options, max_results, dir, |line| { let _ = sender.send(line); });
+                let _ = sender.send(result.unwrap_or_else(|e| format!("error: {}", e)));
+            });
+            state.console.search = Some(receiver);
+            Ok(String::new())
+        },
+        "help" => {
+            Ok(COMMANDS.iter().map(|(_, usage)| *usage).collect::<Vec<_>>().join(", "))
+        },
+        _ => Err(format!("unknown command: {}, try help", command))
+    }
+}
+
+fn single_arg<'a>(args: &[&'a str], usage: &str) -> Result<&'a str, String> {
+    match args {
+        [arg] => Ok(arg),
+        _ => Err(format!("usage: {}", usage))
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(arg: &str) -> Result<T, String> {
+    arg.parse::<T>().map_err(|_| format!("not a number: {}", arg))
+}
+
+fn path_candidates(prefix: &str) -> Vec<String> {
+    let (dir, file_prefix) = match prefix.rfind('/') {
+        Some(i) => prefix.split_at(i + 1),
+        None => ("", prefix)
+    };
+    let entries = match fs::read_dir(if dir.is_empty() { "." } else { dir }) { Ok(e) => e, Err(_) => return Vec::new() };
+
+    let mut candidates: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            if !name.starts_with(file_prefix) { return None }
+            let is_dir = entry.file_type().map(|t| t.is_dir()).unwrap_or(false);
+            Some(format!("{}{}{}", dir, name, if is_dir { "/" } else { "" }))
+        })
+        .collect();
+    candidates.sort();
+    candidates
+}
+
+fn common_prefix(candidates: &[String]) -> String {
+    let first = &candidates[0];
+    let mut length = first.len();
+    for candidate in &candidates[1..] {
+        length = length.min(first.chars().zip(candidate.chars()).take_while(|(a, b)| a == b).count());
+    }
+    first.chars().take(length).collect()
+}
@@ -0,0 +1,52 @@
+use crate::coord::Coord;
+use crate::shapes;
+use crate::state::{State, Tool};
+
+#[derive(Clone, Copy)]
+pub struct Stroke {
+    pub start: Coord,
+    pub last: Coord,
+    pub erase: bool,
+}
+
+// The first cell of a stroke decides whether the whole stroke draws or erases.
+pub fn begin_stroke(state: &mut State, coord: Coord) {
+    let erase = state.is_live(&coord);
+    state.stroke = Some(Stroke { start: coord, last: coord, erase });
+    if state.tool == Tool::Draw { paint(state, &[coord], erase) }
+}
+
+pub fn continue_stroke(state: &mut State, coord: Coord) {
+    let mut stroke = match state.stroke { Some(s) => s, None => return };
+    if state.tool == Tool::Draw {
+        paint(state, &shapes::line(stroke.last, coord), stroke.erase);
+    }
+    stroke.last = coord;
+    state.stroke = Some(stroke);
+}
+
+pub fn end_stroke(state: &mut State) {
+    let stroke = match state.stroke.take() { Some(s) => s, None => return };
+    let erase = stroke.erase;
+    paint(state, &stroke_shape(state.tool, &stroke), erase);
+}
+
+// Cells covered by a shape tool stroke, empty for freehand drawing which paints as it goes.
+pub fn stroke_shape(tool: Tool, stroke: &Stroke) -> Vec<Coord> {
+    match tool {
+        Tool::Line => shapes::line(stroke.start, stroke.last),
+        Tool::Rectangle => shapes::rectangle(stroke.start, stroke.last),
+        Tool::Ellipse => shapes::ellipse(stroke.start, stroke.last),
+        _ => Vec::new()
+    }
+}
+
+fn paint(state: &mut State, coords: &[Coord], erase: bool) {
+    for coord in coords {
+        if erase {
+            state.remove_cell(coord);
+        } else {
+            state.insert_cell(*coord);
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use std::collections::BTreeMap;
+
+use crate::census;
+use crate::coord::Coord;
+use crate::pattern;
+use crate::rule::Rule;
+use crate::stabilization::{self, Ship};
+use crate::universe::Universe;
+
+// How far, in cells, a tracked spaceship may be from where it was expected to be and still count as the same one.
+const TRACKING_TOLERANCE: i32 = 2;
+
+// A spaceship that left the active region.
+pub struct Emission {
+    pub generation: u64,
+    pub name: String,
+    pub direction: &'static str,
+}
+
+// Spaceships of one kind leaving in one direction, with the number of generations between them once they repeat.
+pub struct Stream {
+    pub name: String,
+    pub direction: &'static str,
+    pub count: usize,
+    pub period: Option<u64>,
+}
+
+// An emitted spaceship followed from check to check, so that it is only counted once.
+struct Tracked {
+    dx: i32,
+    dy: i32,
+    period: u64,
+    generation: u64,
+    position: Coord,
+}
+
+// Watches a gun or other emitter for spaceships moving away from everything else, optionally deleting them once
+// they are delete_distance cells away so that the population stays bounded. Looking for spaceships is slow for
+// large patterns, so it can be done every check_interval generations only, at the cost of less precise timing.
+pub struct EmissionTracker {
+    pub emissions: Vec<Emission>,
+    pub delete_distance: Option<i32>,
+    pub check_interval: u64,
+    tracked: Vec<Tracked>,
+}
+
+impl EmissionTracker {
+    pub fn new(delete_distance: Option<i32>, check_interval: u64) -> EmissionTracker {
+        EmissionTracker { emissions: Vec::new(), delete_distance, check_interval: check_interval.max(1), tracked: Vec::new() }
+    }
+
+    pub fn clear(&mut self) {
+        self.emissions.clear();
+        self.tracked.clear();
+    }
+
+    // Records the spaceships that started escaping since the last generation, and deletes the far away ones, returning
+    // the deleted cells.
+    pub fn observe(&mut self, generation: u64, universe: &mut Universe, rule: &Rule) -> Vec<Coord> {
+        let mut deleted = Vec::new();
+        if !generation.is_multiple_of(self.check_interval) { return deleted }
+        let (rest, ships) = stabilization::escaping_ships(universe, rule);
+        let bounds = rest.bounding_box();
+        let mut tracked = Vec::new();
+        for ship in ships {
+            let position = match pattern::bounding_box(&ship.cells) { Some((min, _)) => min, None => continue };
+            if !self.tracked.iter().any(|t| t.follows(&ship, generation, position)) {
+                self.emissions.push(Emission {
+                    generation,
+                    name: census::identify(&ship.cells, rule),
+                    direction: stabilization::direction(ship.dx, ship.dy),
+                });
+            }
+
+            match (self.delete_distance, bounds) {
+                (Some(distance), Some(bounds)) if gap(&ship.cells, bounds) > distance => {
+                    for c in &ship.cells { universe.remove(c); }
+                    deleted.extend(ship.cells);
+                },
+                _ => tracked.push(Tracked { dx: ship.dx, dy: ship.dy, period: ship.period, generation, position })
+            }
+        }
+        self.tracked = tracked;
+        deleted
+    }
+
+    pub fn streams(&self) -> Vec<Stream> {
+        let mut generations: BTreeMap<(&str, &'static str), Vec<u64>> = BTreeMap::new();
+        for e in &self.emissions {
+            generations.entry((&e.name, e.direction)).or_default().push(e.generation);
+        }
+        generations.into_iter().map(|((name, direction), generations)| Stream {
+            name: name.to_string(),
+            direction,
+            count: generations.len(),
+            period: repeat_interval(&generations, self.check_interval),
+        }).collect()
+    }
+
+    // Period of the emitter, the least common multiple of the periods of its streams.
+    pub fn period(&self) -> Option<u64> {
+        let periods: Vec<u64> = self.streams().iter().filter_map(|s| s.period).collect();
+        if periods.is_empty() { return None }
+        Some(periods.iter().fold(1, |period, p| period / gcd(period, *p) * p))
+    }
+
+    pub fn describe(&self) -> String {
+        let mut lines: Vec<String> = self.streams().iter().map(|s| match s.period {
+            Some(period) => format!("{} {} {}, every {} generations", s.count, s.name, s.direction, period),
+            None => format!("{} {} {}", s.count, s.name, s.direction),
+        }).collect();
+        match self.period() {
+            Some(period) => lines.push(format!("{} spaceships emitted, period {}", self.emissions.len(), period)),
+            None => lines.push(format!("{} spaceships emitted", self.emissions.len())),
+        }
+        lines.join("\n")
+    }
+}
+
+impl Tracked {
+    // Whether the ship is where this one would have moved to by the generation.
+    fn follows(&self, ship: &Ship, generation: u64, position: Coord) -> bool {
+        if (ship.dx, ship.dy, ship.period) != (self.dx, self.dy, self.period) { return false }
+        let elapsed = (generation - self.generation) as i32;
+        let expected = Coord::new(
+            self.position.x + self.dx * elapsed / self.period as i32,
+            self.position.y + self.dy * elapsed / self.period as i32
+        );
+        (position.x - expected.x).abs() <= TRACKING_TOLERANCE && (position.y - expected.y).abs() <= TRACKING_TOLERANCE
+    }
+}
+
+// Number of dead cells between the cells and the rectangle, 0 when they overlap.
+fn gap(cells: &[Coord], (min, max): (Coord, Coord)) -> i32 {
+    let (cells_min, cells_max) = match pattern::bounding_box(cells) { Some(b) => b, None => return 0 };
+    let x = (min.x - cells_max.x).max(cells_min.x - max.x);
+    let y = (min.y - cells_max.y).max(cells_min.y - max.y);
+    x.max(y).max(0)
+}
+
+// Smallest number of emissions after which the intervals between them repeat, as the number of generations that
+// spans. Needs at least one full repeat to be sure. Emissions are only noticed at checks, so each one may be seen up
+// to check_interval - 1 generations late and the intervals are averaged.
+fn repeat_interval(generations: &[u64], check_interval: u64) -> Option<u64> {
+    (1..generations.len() / 2 + 1).find_map(|k| {
+        let intervals: Vec<u64> = (k..generations.len()).map(|i| generations[i] - generations[i - k]).collect();
+        let (min, max) = (intervals.iter().min()?, intervals.iter().max()?);
+        if max - min > 2 * (check_interval - 1) { return None }
+        let total: u64 = intervals.iter().sum();
+        Some((total + intervals.len() as u64 / 2) / intervals.len() as u64)
+    })
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rle;
+
+    const GOSPER_GUN: &str = "24bo$22bobo$12b2o6b2o12b2o$11bo3bo4b2o12b2o$2o8bo5bo3b2o$2o8bo3bob2o4bobo$10bo5bo7bo$11bo3bo$12b2o!";
+
+    // Runs the gun for 300 generations, returning the tracker and the largest population of each 90 generations.
+    // Checking every 10 generations keeps the test fast and still times a period 30 stream exactly.
+    fn run_gun(delete_distance: Option<i32>) -> (EmissionTracker, Vec<usize>) {
+        let rule = Rule::life();
+        let mut universe: Universe = rle::parse_pattern(GOSPER_GUN).unwrap().into_iter().collect();
+        let mut tracker = EmissionTracker::new(delete_distance, 10);
+        let mut max_populations = vec![0; 4];
+        for generation in 1..=300 {
+            universe = universe.step(&rule);
+            tracker.observe(generation, &mut universe, &rule);
+            let max = &mut max_populations[(generation as usize - 1) / 90];
+            *max = (*max).max(universe.len());
+        }
+        (tracker, max_populations)
+    }
+
+    #[test]
+    fn finds_the_glider_stream_of_a_gun() {
+        let (tracker, max_populations) = run_gun(None);
+        let streams = tracker.streams();
+        assert_eq!(streams.len(), 1);
+        assert_eq!((streams[0].name.as_str(), streams[0].direction, streams[0].period), ("glider", "SE", Some(30)));
+        assert_eq!(tracker.period(), Some(30));
+        // Three more gliders every 90 generations.
+        assert_eq!(max_populations[2] - max_populations[1], 3 * 5);
+    }
+
+    #[test]
+    fn deleting_far_away_spaceships_bounds_the_population() {
+        let (tracker, max_populations) = run_gun(Some(5));
+        assert_eq!(tracker.emissions.len(), 10);
+        assert_eq!(tracker.period(), Some(30));
+        assert_eq!(max_populations[1], max_populations[2]);
+    }
+
+    #[test]
+    fn finds_the_interval_emissions_repeat_at() {
+        assert_eq!(repeat_interval(&[30, 60, 90, 120], 1), Some(30));
+        // Two streams merged, alternating 10 and 20 generations apart.
+        assert_eq!(repeat_interval(&[0, 10, 30, 40, 60, 70], 1), Some(30));
+        assert_eq!(repeat_interval(&[0, 10, 40, 45], 1), None);
+        // Emissions seen up to 4 generations late.
+        assert_eq!(repeat_interval(&[0, 34, 60, 94, 120], 5), Some(30));
+    }
+}
@@ -0,0 +1,78 @@
+use crate::coord::Coord;
+use crate::rle;
+use crate::state::State;
+use crate::universe::Universe;
+
+// LifeHistory states, https://conwaylife.com/wiki/LifeHistory
+// The marked states 3 and 4 and the boundary state 6 are drawn by hand in Golly and have nothing to come from here.
+const LIFE_HISTORY_ON: u8 = 1;
+const LIFE_HISTORY_TRAIL: u8 = 2;
+const LIFE_HISTORY_START: u8 = 5;
+
+// Starts recording a new trail from the current generation.
+pub fn reset(state: &mut State) {
+    state.history = Some(state.cell_coords.clone());
+    state.history_start = Some(state.cell_coords.clone());
+    state.history_generation = state.generation;
+}
+
+pub fn clear(state: &mut State) {
+    state.history = None;
+    state.history_start = None;
+}
+
+pub fn record(history: &mut Option<Universe>, births: &[Coord]) {
+    if let Some(history) = history {
+        history.extend(births.iter().copied());
+    }
+}
+
+// Live cells in state 1, or 5 if they were already alive at the marked generation, and cells that were alive since
+// then in state 2. Odd states are alive, so start cells that have died since are part of the trail.
+pub fn life_history_states(state: &State) -> Vec<(Coord, u8)> {
+    let started = |c: &Coord| state.history_start.as_ref().is_some_and(|start| start.contains(c));
+    let mut states: Vec<(Coord, u8)> = state.cell_coords.iter()
+        .map(|c| (c, if started(&c) { LIFE_HISTORY_START } else { LIFE_HISTORY_ON }))
+        .collect();
+    if let Some(history) = &state.history {
+        for coord in history.iter() {
+            if !state.is_live(&coord) { states.push((coord, LIFE_HISTORY_TRAIL)) }
+        }
+    }
+    states
+}
+
+pub fn export(state: &State, path: &str) -> Result<(), String> {
+    if state.history.is_none() { return Err("no history recorded".to_string()) }
+    let contents = rle::encode_multistate(&life_history_states(state), "LifeHistory");
+    std::fs::write(path, contents).map_err(|e| format!("could not write {}: {}", path, e))?;
+    println!("exported history since generation {}: {}", state.history_generation, path);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::state_mgmt;
+
+    #[test]
+    fn marks_cells_alive_at_the_reset_as_start_cells() {
+        let (mut state, config) = (State::new(), Config::new());
+        for x in 0..3 { state.insert_cell(Coord::new(x, 0)); }
+        reset(&mut state);
+        state_mgmt::step(&mut state, &config);
+
+        let mut states: Vec<(i32, i32, u8)> = life_history_states(&state).iter().map(|(c, s)| (c.x, c.y, *s)).collect();
+        states.sort();
+        assert_eq!(states, vec![(0, 0, 2), (1, -1, 1), (1, 0, 5), (1, 1, 1), (2, 0, 2)]);
+    }
+
+    #[test]
+    fn exports_nothing_without_a_history() {
+        let state = State::new();
+        assert!(life_history_states(&state).is_empty());
+        assert!(export(&state, "unused.rle").is_err());
+    }
+}
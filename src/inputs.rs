@@ -1,79 +1,420 @@
-use sdl2::{keyboard::{self, Keycode}, event::Event};
+use sdl2::{keyboard::{Keycode, Scancode}, event::{Event, WindowEvent}};
 
-use crate::{utils, state::State, config::Config};
+use crate::{utils, state_mgmt, state::{State, Tool}, config::Config, svg, selection::{self, Selection}, drawing, coloring, history, minimap, console, library, stamp, bookmarks, census, population, emission::EmissionTracker, period::PeriodDetector, predecessor, camera::{self, Anchor}};
+use crate::bindings::{Action, Input, Modifiers};
 
 pub fn handle_inputs(state: &mut State, event_pump: &mut sdl2::EventPump, config: &Config) -> () {
-    if event_pump.keyboard_state().is_scancode_pressed(keyboard::Scancode::Down) {
-        state.camera_y_i = 1.0
-    } else if event_pump.keyboard_state().is_scancode_pressed(keyboard::Scancode::Up) {
-        state.camera_y_i = -1.0
-    } else {
-        state.camera_y_i = 0.0
-    }
-
-    if event_pump.keyboard_state().is_scancode_pressed(keyboard::Scancode::Right) {
-        state.camera_x_i = 1.0
-    } else if event_pump.keyboard_state().is_scancode_pressed(keyboard::Scancode::Left) {
-        state.camera_x_i = -1.0
-    } else {
-        state.camera_x_i = 0.0
+    state.camera_y_i = held_axis(event_pump, config, Action::PanUp, Action::PanDown);
+    state.camera_x_i = held_axis(event_pump, config, Action::PanLeft, Action::PanRight);
+    state.camera_z_i = held_axis(event_pump, config, Action::ZoomOut, Action::ZoomIn);
+    if state.console.open || state.library.open {
+        state.camera_x_i = 0.0;
+        state.camera_y_i = 0.0;
+        state.camera_z_i = 0.0;
     }
+    if state.camera_x_i != 0.0 || state.camera_y_i != 0.0 { camera::stop(state) }
+    if state.camera_z_i != 0.0 { camera::stop_zooming(state) }
 
-    if event_pump.keyboard_state().is_scancode_pressed(keyboard::Scancode::Z) {
-        state.camera_z_i = -1.0
-    } else if event_pump.keyboard_state().is_scancode_pressed(keyboard::Scancode::X) {
-        state.camera_z_i = 1.0
-    } else {
-        state.camera_z_i = 0.0
-    }
-
-    state.cursor_x = event_pump.mouse_state().x();
-    state.cursor_y = event_pump.mouse_state().y();
+    state.cursor_x = utils::to_pixels(event_pump.mouse_state().x(), config);
+    state.cursor_y = utils::to_pixels(event_pump.mouse_state().y(), config);
+    let modifiers = held_modifiers(event_pump);
 
     for event in event_pump.poll_iter() {
         match event {
-            Event::Quit { ..  } |
-            Event::KeyDown { keycode: Some(Keycode::Escape), .. } => {
+            Event::Quit { ..  } => {
                 state.running = false;
             },
-            Event::KeyDown { keycode: Some(Keycode::Space), .. } => {
-                state.paused = !state.paused;
-            },
-            Event::KeyDown { keycode: Some(Keycode::Plus), .. } => {
-                if state.cell_update_interval > config.dt { state.cell_update_interval /= 2.0 }
+            Event::Window { win_event: WindowEvent::SizeChanged(..), .. } => {
+                state.viewport_changed = true;
             },
-            Event::KeyDown { keycode: Some(Keycode::Minus), .. } => {
-                if state.cell_update_interval < 5000.0 { state.cell_update_interval *= 2.0 }
+            Event::KeyDown { keycode: Some(keycode), .. } if state.console.open => {
+                console_key(state, config, keycode);
             },
-            Event::KeyDown { keycode: Some(Keycode::R), .. } => {
-                state.reset_camera();
+            Event::TextInput { text, .. } if state.console.open => {
+                state.console.input.extend(text.chars().filter(|c| *c != '`'));
             },
-            Event::KeyDown { keycode: Some(Keycode::P), .. } => {
-                for coord in &state.cell_coords {
-                    println!("{}", coord.x);
-                    println!("{}", coord.y);
+            Event::KeyDown { keycode: Some(keycode), keymod, .. } => {
+                if state.library.open && library_key(state, config, keycode) { continue }
+                if let Some(action) = config.bindings.action(Input::Key(keycode), Modifiers::from_keymod(keymod)) {
+                    press(state, config, action, state.cursor_x, state.cursor_y);
                 }
             },
-            Event::KeyDown { keycode: Some(Keycode::C), .. } => {
-                let center_coord = utils::game_coord(config.window_width / 2.0, config.window_height / 2.0, state);
-                println!("center coord: {}", center_coord);
+            Event::KeyUp { timestamp, keycode: Some(keycode), keymod, .. } => {
+                if let Some(action) = config.bindings.action(Input::Key(keycode), Modifiers::from_keymod(keymod)) {
+                    release(state, config, action, timestamp);
+                }
             },
-            Event::KeyDown { keycode: Some(Keycode::F), .. } => {
-                println!("FPS: {}", state.timestep.frame_rate().unwrap_or(0));
+            Event::MouseButtonDown { mouse_btn, clicks, x, y, .. } => {
+                let (x, y) = (utils::to_pixels(x, config), utils::to_pixels(y, config));
+                // Clicks with modifiers that aren't bound do what the plain click does.
+                let input = Input::Mouse(mouse_btn);
+                match config.bindings.action(input, modifiers).or_else(|| config.bindings.action(input, Modifiers::default())) {
+                    Some(Action::UseTool) if library::contains(state, config, x as f32) => click_library(state, config, x, y, clicks),
+                    Some(action) => press(state, config, action, x, y),
+                    None => {}
+                }
             },
-            Event::KeyDown { keycode: Some(Keycode::D), .. } => {
-                println!("State: {}", state);
+            Event::MouseButtonUp { timestamp, mouse_btn, .. } => {
+                for action in config.bindings.actions_for(Input::Mouse(mouse_btn)) {
+                    release(state, config, action, timestamp);
+                }
             },
-            Event::MouseButtonDown { x, y, .. } => {
-                let coord = utils::game_coord(x as f32, y as f32, state);
-                println!("x: {}, y: {}, coord: {}", x, y, coord);
-                if state.cell_coords.contains(&coord) {
-                    state.cell_coords.remove(&coord);
-                } else {
-                    state.cell_coords.insert(coord);
+            Event::MouseMotion { timestamp, xrel, yrel, .. } if state.panning => {
+                let (xrel, yrel) = (utils::to_pixels(xrel, config) as f32, utils::to_pixels(yrel, config) as f32);
+                state.camera_x -= xrel;
+                state.camera_y -= yrel;
+                state.pan_motions.push_back((timestamp, xrel, yrel));
+                while state.pan_motions.front().is_some_and(|(t, _, _)| timestamp.saturating_sub(*t) > config.pan_fling_window_ms) {
+                    state.pan_motions.pop_front();
                 }
             },
+            Event::MouseMotion { x, y, .. } if state.minimap_dragging => {
+                navigate_minimap(state, config, utils::to_pixels(x, config), utils::to_pixels(y, config));
+            },
+            Event::MouseMotion { x, y, .. } if state.selecting => {
+                let coord = utils::game_coord(utils::to_pixels(x, config) as f32, utils::to_pixels(y, config) as f32, state);
+                if let Some(selection) = state.selection.as_mut() { selection.end = coord }
+            },
+            Event::MouseMotion { x, y, .. } if state.stroke.is_some() => {
+                let coord = utils::game_coord(utils::to_pixels(x, config) as f32, utils::to_pixels(y, config) as f32, state);
+                drawing::continue_stroke(state, coord);
+            },
+            Event::MouseWheel { y, .. } if library::contains(state, config, state.cursor_x as f32) => {
+                library::select(state, -y);
+            },
+            Event::MouseWheel { precise_y, .. } => {
+                camera::stop_zooming(state);
+                let factor = config.wheel_zoom_factor.powf(precise_y);
+                state_mgmt::zoom_at(state, config, factor, state.cursor_x as f32, state.cursor_y as f32);
+            },
+            Event::MultiGesture { d_dist, x, y, num_fingers: 2, .. } => {
+                camera::stop_zooming(state);
+                let factor = 1.0 + d_dist * config.pinch_zoom_sensitivity;
+                state_mgmt::zoom_at(state, config, factor, x * config.window_width, y * config.window_height);
+            },
             _ => {}
         }
     }
 }
+
+// Performs the action bound to a pressed key or mouse button, (x, y) is the pointer position in drawable pixels.
+fn press(state: &mut State, config: &Config, action: Action, x: i32, y: i32) {
+    match action {
+        Action::Quit => {
+            state.running = false;
+        },
+        Action::ToggleFullscreen => {
+            state.fullscreen = !state.fullscreen;
+            state.viewport_changed = true;
+        },
+        Action::TogglePause => {
+            state.paused = !state.paused;
+        },
+        Action::SpeedUp => {
+            if state.cell_update_interval > config.dt { state.cell_update_interval /= 2.0 }
+        },
+        Action::SlowDown => {
+            if state.cell_update_interval < 5000.0 { state.cell_update_interval *= 2.0 }
+        },
+        Action::ResetCamera => {
+            camera::stop(state);
+            state.reset_camera();
+        },
+        Action::FollowCentroid => camera::toggle_follow(state, Anchor::Centroid),
+        Action::FollowBoundingBox => camera::toggle_follow(state, Anchor::BoundingBox),
+        Action::AutoFit => camera::toggle_auto_fit(state),
+        Action::GoTo => {
+            state.console.open = true;
+            state.console.input = "goto ".to_string();
+        },
+        Action::GoToBookmark(slot) => bookmarks::go_to_slot(state, config, slot),
+        Action::SetBookmark(slot) => bookmarks::save_slot(state, config, slot),
+        Action::NextBookmark => bookmarks::cycle(state, config, 1),
+        Action::PreviousBookmark => bookmarks::cycle(state, config, -1),
+        Action::Pan => {
+            camera::stop(state);
+            state.panning = true;
+            state.pan_velocity_x = 0.0;
+            state.pan_velocity_y = 0.0;
+            state.pan_motions.clear();
+        },
+        Action::UseTool => {
+            if on_minimap(state, config, x, y) {
+                state.minimap_dragging = true;
+                navigate_minimap(state, config, x, y);
+                return
+            }
+            let coord = utils::game_coord(x as f32, y as f32, state);
+            println!("x: {}, y: {}, coord: {}", x, y, coord);
+            match state.tool {
+                Tool::Select => {
+                    state.selection = Some(Selection::new(coord, coord));
+                    state.selecting = true;
+                },
+                Tool::Stamp => stamp::place(state, coord),
+                _ => drawing::begin_stroke(state, coord)
+            }
+        },
+        Action::SelectTool => state.toggle_tool(Tool::Select),
+        Action::LineTool => state.toggle_tool(Tool::Line),
+        Action::RectangleTool => state.toggle_tool(Tool::Rectangle),
+        Action::EllipseTool => state.toggle_tool(Tool::Ellipse),
+        Action::StampTool => stamp::toggle_tool(state),
+        Action::StampClipboard => {
+            if let Err(e) = stamp::pick_clipboard(state) { println!("could not stamp clipboard: {}", e) }
+        },
+        Action::CycleStampMode => {
+            if let Some(stamp) = state.stamp.as_mut() {
+                stamp.mode = stamp.mode.next();
+                println!("stamp mode: {:?}", stamp.mode);
+            }
+        },
+        Action::Copy => selection::copy(state),
+        Action::Cut => selection::cut(state),
+        Action::Paste => {
+            let coord = utils::game_coord(x as f32, y as f32, state);
+            if let Err(e) = selection::paste(state, coord) { println!("could not paste: {}", e) }
+        },
+        Action::SaveSelection => {
+            if let Err(e) = selection::save(state, config.selection_path) { println!("could not save selection: {}", e) }
+        },
+        Action::LoadClipboard => {
+            if let Err(e) = selection::load_clipboard(state, config.selection_path) { println!("could not load clipboard: {}", e) }
+        },
+        Action::ClearSelection => selection::clear_inside(state),
+        Action::ClearOutsideSelection => selection::clear_outside(state),
+        Action::RandomFill => selection::random_fill(state, config.random_fill_density),
+        Action::RotateSelection | Action::FlipHorizontal | Action::FlipVertical if state.tool == Tool::Stamp => {
+            let stamp = match state.stamp.as_mut() { Some(s) => s, None => return };
+            match action {
+                Action::RotateSelection => stamp.rotate(),
+                Action::FlipHorizontal => stamp.flip_horizontal(),
+                _ => stamp.flip_vertical()
+            }
+        },
+        Action::RotateSelection => selection::rotate(state),
+        Action::FlipHorizontal => selection::flip_horizontal(state),
+        Action::FlipVertical => selection::flip_vertical(state),
+        Action::ResetHistory => history::reset(state),
+        Action::ClearHistory => history::clear(state),
+        Action::ExportHistory => {
+            if let Err(e) = history::export(state, config.history_path) { println!("could not export history: {}", e) }
+        },
+        Action::CycleRenderMode => {
+            state.set_render_mode(state.render_mode.next());
+            println!("render mode: {:?}", state.render_mode);
+        },
+        Action::ToggleMinimap => {
+            state.minimap_visible = !state.minimap_visible;
+        },
+        Action::ExportSvg => {
+            let options = svg::SvgOptions::new(config);
+            let cell_color = |coord: &_| coloring::cell_color(state, config, coord);
+            if let Err(e) = svg::export_svg(config.svg_path, state, config, &options, &cell_color) {
+                println!("could not export svg: {}", e);
+            }
+        },
+        Action::ToggleHelp => {
+            state.help_visible = !state.help_visible;
+        },
+        Action::ToggleHud => {
+            state.hud_visible = !state.hud_visible;
+        },
+        Action::Census => {
+            for (name, count) in census::census_of_state(state, config) {
+                println!("{} {}", count, name);
+            }
+        },
+        Action::ToggleGraph => {
+            state.graph_visible = !state.graph_visible;
+        },
+        Action::ExportPopulation => {
+            if let Err(e) = population::save_csv(config.population_csv_path, &state.population_history) {
+                println!("could not export population: {}", e);
+            }
+        },
+        Action::ToggleEmissions => {
+            // Turning tracking off prints what was counted.
+            match state.emission_tracker.take() {
+                Some(tracker) => println!("{}", tracker.describe()),
+                None => state.emission_tracker = Some(EmissionTracker::new(config.emission_delete_distance, config.emission_check_interval))
+            }
+        },
+        Action::TogglePeriod => {
+            state.periodicity = None;
+            match state.period_detector.take() {
+                Some(_) => println!("period detection off"),
+                None => {
+                    state.period_detector = Some(PeriodDetector::new(config.period_history));
+                    println!("period detection on")
+                }
+            }
+        },
+        Action::FindPredecessor => {
+            match predecessor::find_for_selection(state, config) {
+                Ok(message) => println!("{}", message),
+                Err(e) => println!("could not find a predecessor: {}", e)
+            }
+        },
+        Action::ToggleConsole => {
+            state.console.open = !state.console.open;
+        },
+        Action::ToggleLibrary => library::toggle(state, config),
+        Action::PrintCells => {
+            for coord in state.cell_coords.iter() {
+                println!("{}", coord.x);
+                println!("{}", coord.y);
+            }
+        },
+        Action::PrintCenter => {
+            let center_coord = utils::game_coord(config.window_width / 2.0, config.window_height / 2.0, state);
+            println!("center coord: {}", center_coord);
+        },
+        Action::PrintFps => {
+            println!("FPS: {}", state.timestep.frame_rate().unwrap_or(0));
+        },
+        Action::PrintState => {
+            println!("State: {}", state);
+        },
+        Action::PanUp | Action::PanDown | Action::PanLeft | Action::PanRight | Action::ZoomIn | Action::ZoomOut => {}
+    }
+}
+
+// timestamp is the time of the release event in milliseconds.
+fn release(state: &mut State, config: &Config, action: Action, timestamp: u32) {
+    match action {
+        Action::Pan => {
+            state.panning = false;
+            fling(state, config, timestamp);
+        },
+        Action::UseTool => {
+            state.minimap_dragging = false;
+            state.selecting = false;
+            drawing::end_stroke(state);
+        },
+        _ => {}
+    }
+}
+
+// Keeps the camera moving at the average speed of the drag over the motions of the last pan_fling_window_ms, from
+// the oldest of them to the release, converted from pixels per millisecond to pixels per update. A drag that stopped
+// before the release doesn't fling.
+fn fling(state: &mut State, config: &Config, timestamp: u32) {
+    let window = config.pan_fling_window_ms;
+    let recent: Vec<&(u32, f32, f32)> = state.pan_motions.iter().filter(|(t, _, _)| timestamp.saturating_sub(*t) <= window).collect();
+    let (dx, dy) = recent.iter().fold((0.0, 0.0), |(dx, dy), (_, x, y)| (dx + x, dy + y));
+    let duration = recent.first().map_or(1, |(t, _, _)| timestamp.saturating_sub(*t).max(1));
+    state.pan_velocity_x = -dx / duration as f32 * config.dt;
+    state.pan_velocity_y = -dy / duration as f32 * config.dt;
+    state.pan_motions.clear();
+}
+
+// Editing keys of the console, everything else arrives as text input.
+fn console_key(state: &mut State, config: &Config, keycode: Keycode) {
+    match keycode {
+        Keycode::Return | Keycode::KpEnter => console::submit(state, config),
+        Keycode::Backspace => { state.console.input.pop(); },
+        Keycode::Up => state.console.history_previous(),
+        Keycode::Down => state.console.history_next(),
+        Keycode::Tab => state.console.complete(),
+        Keycode::Escape | Keycode::Backquote => state.console.open = false,
+        _ => {}
+    }
+}
+
+// Navigation keys of the pattern library, returns false for keys it leaves to the bindings.
+fn library_key(state: &mut State, config: &Config, keycode: Keycode) -> bool {
+    let page = library::layout(state, config).rows as i32;
+    match keycode {
+        Keycode::Up => library::select(state, -1),
+        Keycode::Down => library::select(state, 1),
+        Keycode::PageUp => library::select(state, -page),
+        Keycode::PageDown => library::select(state, page),
+        Keycode::Home => library::select_index(state, 0),
+        Keycode::End => library::select_index(state, state.library.patterns.len().saturating_sub(1)),
+        Keycode::Return | Keycode::KpEnter => library::load_selected(state, config),
+        Keycode::Space => library::stamp_selected(state),
+        Keycode::Escape => state.library.open = false,
+        _ => return false
+    }
+    true
+}
+
+// Selects the clicked pattern, a double click loads it.
+fn click_library(state: &mut State, config: &Config, x: i32, y: i32, clicks: u8) {
+    let index = match library::row_at(state, config, x as f32, y as f32) { Some(i) => i, None => return };
+    library::select_index(state, index);
+    if clicks >= 2 { library::load_selected(state, config) }
+}
+
+// -1.0 while the negative action is held, 1.0 while the positive one is, 0.0 otherwise.
+fn held_axis(event_pump: &sdl2::EventPump, config: &Config, negative: Action, positive: Action) -> f32 {
+    if is_held(event_pump, config, positive) {
+        1.0
+    } else if is_held(event_pump, config, negative) {
+        -1.0
+    } else {
+        0.0
+    }
+}
+
+// Modifier keys currently held down, the command key counting as control like in Modifiers::from_keymod.
+fn held_modifiers(event_pump: &sdl2::EventPump) -> Modifiers {
+    let keyboard_state = event_pump.keyboard_state();
+    let pressed = |scancodes: &[Scancode]| scancodes.iter().any(|s| keyboard_state.is_scancode_pressed(*s));
+    Modifiers {
+        ctrl: pressed(&[Scancode::LCtrl, Scancode::RCtrl, Scancode::LGui, Scancode::RGui]),
+        shift: pressed(&[Scancode::LShift, Scancode::RShift]),
+        alt: pressed(&[Scancode::LAlt, Scancode::RAlt]),
+    }
+}
+
+// Modifiers have to match exactly like for pressed actions, shift aside, so that e.g. Ctrl+X doesn't also zoom with X.
+fn is_held(event_pump: &sdl2::EventPump, config: &Config, action: Action) -> bool {
+    let keyboard_state = event_pump.keyboard_state();
+    let modifiers = held_modifiers(event_pump);
+    let unshifted = Modifiers { shift: false, ..modifiers };
+
+    config.bindings.bindings_for(action).any(|binding| {
+        (binding.modifiers == modifiers || binding.modifiers == unshifted) && match binding.input {
+            Input::Key(keycode) => Scancode::from_keycode(keycode).is_some_and(|s| keyboard_state.is_scancode_pressed(s)),
+            Input::Mouse(button) => event_pump.mouse_state().is_mouse_button_pressed(button),
+        }
+    })
+}
+
+fn on_minimap(state: &State, config: &Config, x: i32, y: i32) -> bool {
+    minimap::layout(state, config).is_some_and(|m| m.contains(x as f32, y as f32))
+}
+
+fn navigate_minimap(state: &mut State, config: &Config, x: i32, y: i32) {
+    camera::stop(state);
+    if let Some(m) = minimap::layout(state, config) { minimap::navigate(state, config, &m, x as f32, y as f32) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flung(motions: &[(u32, f32, f32)], release: u32) -> (f32, f32) {
+        let config = Config::new();
+        let mut state = State::new();
+        state.pan_motions.extend(motions.iter().copied());
+        fling(&mut state, &config, release);
+        (state.pan_velocity_x / config.dt, state.pan_velocity_y / config.dt)
+    }
+
+    #[test]
+    fn flings_at_the_speed_of_the_recent_drag() {
+        // 20 pixels right over 10 ms, 2 pixels per millisecond, moving the camera left.
+        assert_eq!(flung(&[(100, 10.0, 0.0), (105, 5.0, 0.0), (110, 5.0, -4.0)], 110), (-2.0, 0.4));
+        // A short fast flick isn't averaged over the whole window.
+        assert_eq!(flung(&[(200, 30.0, 0.0)], 203), (-10.0, 0.0));
+    }
+
+    #[test]
+    fn ignores_drags_that_stopped_before_the_release() {
+        assert_eq!(flung(&[(100, 50.0, 50.0)], 1000), (0.0, 0.0));
+        assert_eq!(flung(&[], 1000), (0.0, 0.0));
+    }
+}
@@ -7,3 +7,30 @@ pub mod state;
 pub mod timestep;
 pub mod coord;
 pub mod rle;
+pub mod svg;
+pub mod pattern;
+pub mod selection;
+pub mod shapes;
+pub mod drawing;
+pub mod universe;
+pub mod coloring;
+pub mod history;
+pub mod minimap;
+pub mod bindings;
+pub mod rule;
+pub mod console;
+pub mod library;
+pub mod stamp;
+pub mod camera;
+pub mod bookmarks;
+pub mod session;
+pub mod period;
+pub mod census;
+pub mod apgcode;
+pub mod soup;
+pub mod stabilization;
+pub mod population;
+pub mod emission;
+pub mod sat;
+pub mod predecessor;
+pub mod search;
@@ -0,0 +1,217 @@
+use std::fs;
+
+use crate::config::Config;
+use crate::minimap::{self, Minimap};
+use crate::rle;
+use crate::rule::Rule;
+use crate::stamp;
+use crate::state::State;
+use crate::state_mgmt;
+use crate::universe::Universe;
+
+pub struct PatternInfo {
+    pub path: String,
+    pub name: String,
+    pub author: Option<String>,
+    pub width: i32,
+    pub height: i32,
+}
+
+// Browser over the pattern files of the configured directories, with a running preview of the selected one.
+pub struct Library {
+    pub open: bool,
+    pub patterns: Vec<PatternInfo>,
+    pub selected: usize,
+    pub preview: Universe,
+    preview_rule: Rule,
+    t_since_last_preview_update: f32,
+}
+
+impl Library {
+    pub fn new() -> Library {
+        Library {
+            open: false,
+            patterns: Vec::new(),
+            selected: 0,
+            preview: Universe::new(),
+            preview_rule: Rule::life(),
+            t_since_last_preview_update: 0.0,
+        }
+    }
+
+    pub fn selected_pattern(&self) -> Option<&PatternInfo> {
+        self.patterns.get(self.selected)
+    }
+}
+
+impl Default for Library {
+    fn default() -> Library {
+        Library::new()
+    }
+}
+
+// Position of the list rows and the preview in the panel on the left side of the window.
+pub struct Layout {
+    pub width: f32,
+    pub margin: f32,
+    pub row_height: f32,
+    pub rows: usize,
+    pub first_row: usize,
+    pub preview_y: f32,
+    pub info_y: f32,
+}
+
+const INFO_LINES: usize = 4;
+
+pub fn layout(state: &State, config: &Config) -> Layout {
+    let width = config.library_width.min(config.window_width);
+    let margin = config.small_char_height / 2.0;
+    let row_height = config.small_char_height;
+    let preview_size = width - 2.0 * margin;
+    let list_height = config.window_height - preview_size - (INFO_LINES as f32 + 1.0) * row_height - 4.0 * margin;
+    let rows = ((list_height / row_height) as usize).max(1);
+
+    Layout {
+        width,
+        margin,
+        row_height,
+        rows,
+        first_row: (state.library.selected + 1).saturating_sub(rows),
+        preview_y: margin + rows as f32 * row_height + margin,
+        info_y: 3.0 * margin + rows as f32 * row_height + preview_size,
+    }
+}
+
+pub fn preview_view(state: &State, config: &Config) -> Option<Minimap> {
+    let layout = layout(state, config);
+    let size = layout.width - 2.0 * layout.margin;
+    minimap::fit(&state.library.preview, layout.margin, layout.preview_y, size, size)
+}
+
+// Index of the pattern listed at the given window position.
+pub fn row_at(state: &State, config: &Config, x: f32, y: f32) -> Option<usize> {
+    let layout = layout(state, config);
+    if x >= layout.width || y < layout.margin { return None }
+    let row = ((y - layout.margin) / layout.row_height) as usize;
+    let index = layout.first_row + row;
+    if row < layout.rows && index < state.library.patterns.len() { Some(index) } else { None }
+}
+
+pub fn contains(state: &State, config: &Config, x: f32) -> bool {
+    state.library.open && x < layout(state, config).width
+}
+
+// Extra pattern directories listed one per line in the given file, a missing file means none.
+pub fn read_dirs(path: &str) -> Vec<String> {
+    let contents = match fs::read_to_string(path) { Ok(c) => c, Err(_) => return Vec::new() };
+    contents.lines()
+        .map(|l| l.trim())
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(|l| l.to_string())
+        .collect()
+}
+
+// Every RLE file of the directories, sorted by file name within each directory.
+pub fn scan(dirs: &[String]) -> Vec<PatternInfo> {
+    let mut patterns = Vec::new();
+    for dir in dirs {
+        let entries = match fs::read_dir(dir) {
+            Ok(e) => e,
+            Err(e) => { println!("could not read pattern directory {}: {}", dir, e); continue }
+        };
+        let mut paths: Vec<_> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|e| e.eq_ignore_ascii_case("rle")))
+            .collect();
+        paths.sort();
+
+        for path in paths {
+            let contents = match fs::read_to_string(&path) { Ok(c) => c, Err(_) => continue };
+            let header = rle::parse_header(&contents);
+            let file_name = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+            patterns.push(PatternInfo {
+                path: path.to_string_lossy().to_string(),
+                name: header.name.map(|n| n.trim_end_matches(".rle").to_string()).unwrap_or(file_name),
+                author: header.author,
+                width: header.width,
+                height: header.height,
+            });
+        }
+    }
+    patterns
+}
+
+pub fn toggle(state: &mut State, config: &Config) {
+    if state.library.open {
+        state.library.open = false;
+        return
+    }
+
+    state.library.patterns = scan(&config.pattern_dirs);
+    state.library.selected = state.library.patterns.iter()
+        .position(|p| state.pattern_path.as_deref() == Some(p.path.as_str()))
+        .unwrap_or(0);
+    state.library.open = true;
+    load_preview(state);
+}
+
+pub fn select(state: &mut State, offset: i32) {
+    let count = state.library.patterns.len() as i32;
+    if count == 0 { return }
+    let selected = (state.library.selected as i32 + offset).clamp(0, count - 1) as usize;
+    select_index(state, selected);
+}
+
+pub fn select_index(state: &mut State, index: usize) {
+    if index == state.library.selected { return }
+    state.library.selected = index;
+    load_preview(state);
+}
+
+fn load_preview(state: &mut State) {
+    let library = &mut state.library;
+    library.t_since_last_preview_update = 0.0;
+    let path = match library.selected_pattern() { Some(p) => p.path.clone(), None => return };
+    let contents = fs::read_to_string(&path).unwrap_or_default();
+    library.preview = rle::parse_pattern(&contents).unwrap_or_default().into_iter().collect();
+    library.preview_rule = rle::parse_rule(&contents).unwrap_or_else(Rule::life);
+}
+
+// Runs the preview at its own pace while the browser is open.
+pub fn update(state: &mut State, config: &Config) {
+    let library = &mut state.library;
+    if !library.open { return }
+    library.t_since_last_preview_update += config.dt;
+    if library.t_since_last_preview_update < config.library_preview_interval { return }
+    library.t_since_last_preview_update -= config.library_preview_interval;
+    library.preview = library.preview.step(&library.preview_rule);
+}
+
+// Replaces the universe with the pattern and centers the camera on it.
+pub fn load(state: &mut State, config: &Config, path: &str) -> Result<(), String> {
+    state.reset_cell_coords();
+    rle::load_pattern(path, state, 0, 0)?;
+    state.pattern_path = Some(path.to_string());
+    if let Some((min, max)) = state.cell_coords.bounding_box() {
+        state_mgmt::center_on(state, config, (min.x + max.x + 1) as f32 / 2.0, (min.y + max.y + 1) as f32 / 2.0);
+    }
+    Ok(())
+}
+
+pub fn load_selected(state: &mut State, config: &Config) {
+    let path = match state.library.selected_pattern() { Some(p) => p.path.clone(), None => return };
+    match load(state, config, &path) {
+        Ok(()) => state.library.open = false,
+        Err(e) => println!("could not load pattern: {}", e)
+    }
+}
+
+// Picks the pattern for the stamp tool and closes the browser so it can be placed.
+pub fn stamp_selected(state: &mut State) {
+    let path = match state.library.selected_pattern() { Some(p) => p.path.clone(), None => return };
+    match stamp::pick_file(state, &path) {
+        Ok(()) => state.library.open = false,
+        Err(e) => println!("could not stamp pattern: {}", e)
+    }
+}
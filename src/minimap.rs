@@ -0,0 +1,65 @@
+use crate::config::Config;
+use crate::state::State;
+use crate::state_mgmt;
+use crate::universe::Universe;
+
+// Placement of the minimap in the window and the part of the universe it shows.
+pub struct Minimap {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    pub origin_x: f32,
+    pub origin_y: f32,
+    pub scale: f32,
+}
+
+impl Minimap {
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    }
+
+    pub fn to_window(&self, cell_x: f32, cell_y: f32) -> (f32, f32) {
+        (self.x + (cell_x - self.origin_x) * self.scale, self.y + (cell_y - self.origin_y) * self.scale)
+    }
+
+    pub fn to_cell(&self, x: f32, y: f32) -> (f32, f32) {
+        (self.origin_x + (x - self.x) / self.scale, self.origin_y + (y - self.y) / self.scale)
+    }
+}
+
+// Fits the bounding box of the whole pattern into the bottom right corner of the window.
+pub fn layout(state: &State, config: &Config) -> Option<Minimap> {
+    if !state.minimap_visible { return None }
+    fit(
+        &state.cell_coords,
+        config.window_width - config.minimap_width - config.minimap_margin,
+        config.window_height - config.minimap_height - config.minimap_margin,
+        config.minimap_width,
+        config.minimap_height
+    )
+}
+
+// Scales the bounding box of the universe to fit the given window rectangle, centered.
+pub fn fit(universe: &Universe, x: f32, y: f32, width: f32, height: f32) -> Option<Minimap> {
+    let (min, max) = universe.bounding_box()?;
+    let pattern_width = (max.x - min.x + 1) as f32;
+    let pattern_height = (max.y - min.y + 1) as f32;
+    let scale = (width / pattern_width).min(height / pattern_height);
+
+    Some(Minimap {
+        x,
+        y,
+        width,
+        height,
+        origin_x: min.x as f32 - (width / scale - pattern_width) / 2.0,
+        origin_y: min.y as f32 - (height / scale - pattern_height) / 2.0,
+        scale,
+    })
+}
+
+// Centers the camera on the cell under the given window position of the minimap.
+pub fn navigate(state: &mut State, config: &Config, minimap: &Minimap, x: f32, y: f32) {
+    let (cell_x, cell_y) = minimap.to_cell(x, y);
+    state_mgmt::center_on(state, config, cell_x, cell_y);
+}
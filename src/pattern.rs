@@ -0,0 +1,89 @@
+use crate::coord::Coord;
+
+// Smallest and largest corner of the rectangle enclosing all cells.
+pub fn bounding_box(cells: &[Coord]) -> Option<(Coord, Coord)> {
+    let first = cells.first()?;
+    let mut min = *first;
+    let mut max = *first;
+    for coord in cells {
+        min.x = min.x.min(coord.x);
+        min.y = min.y.min(coord.y);
+        max.x = max.x.max(coord.x);
+        max.y = max.y.max(coord.y);
+    }
+    Some((min, max))
+}
+
+pub fn translate(cells: &[Coord], dx: i32, dy: i32) -> Vec<Coord> {
+    cells.iter().map(|c| Coord::new(c.x + dx, c.y + dy)).collect()
+}
+
+// Moves the cells so that their bounding box starts at (0, 0).
+pub fn normalize(cells: &[Coord]) -> Vec<Coord> {
+    match bounding_box(cells) {
+        Some((min, _)) => translate(cells, -min.x, -min.y),
+        None => Vec::new()
+    }
+}
+
+// Rotates 90 degrees clockwise, keeping the bounding box anchored at its top left corner.
+pub fn rotate_cw(cells: &[Coord]) -> Vec<Coord> {
+    let (min, max) = match bounding_box(cells) { Some(b) => b, None => return Vec::new() };
+    cells.iter().map(|c| Coord::new(min.x + max.y - c.y, min.y + c.x - min.x)).collect()
+}
+
+pub fn flip_horizontal(cells: &[Coord]) -> Vec<Coord> {
+    let (min, max) = match bounding_box(cells) { Some(b) => b, None => return Vec::new() };
+    cells.iter().map(|c| Coord::new(min.x + max.x - c.x, c.y)).collect()
+}
+
+pub fn flip_vertical(cells: &[Coord]) -> Vec<Coord> {
+    let (min, max) = match bounding_box(cells) { Some(b) => b, None => return Vec::new() };
+    cells.iter().map(|c| Coord::new(c.x, min.y + max.y - c.y)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cells(coords: &[(i32, i32)]) -> Vec<Coord> {
+        coords.iter().map(|(x, y)| Coord::new(*x, *y)).collect()
+    }
+
+    fn sorted(cells: &[Coord]) -> Vec<(i32, i32)> {
+        let mut cells: Vec<(i32, i32)> = cells.iter().map(|c| (c.x, c.y)).collect();
+        cells.sort();
+        cells
+    }
+
+    // An L shape at (10, 20): three cells down and one to the right at the bottom.
+    fn l_shape() -> Vec<Coord> {
+        cells(&[(10, 20), (10, 21), (10, 22), (11, 22)])
+    }
+
+    #[test]
+    fn finds_bounding_boxes() {
+        assert!(bounding_box(&[]).is_none());
+        let (min, max) = bounding_box(&l_shape()).unwrap();
+        assert_eq!((min.x, min.y, max.x, max.y), (10, 20, 11, 22));
+    }
+
+    #[test]
+    fn normalizes_to_the_origin() {
+        assert_eq!(sorted(&normalize(&l_shape())), vec![(0, 0), (0, 1), (0, 2), (1, 2)]);
+    }
+
+    #[test]
+    fn rotates_clockwise_around_the_top_left_corner() {
+        assert_eq!(sorted(&rotate_cw(&l_shape())), vec![(10, 20), (10, 21), (11, 20), (12, 20)]);
+        let four_times = (0..4).fold(l_shape(), |cells, _| rotate_cw(&cells));
+        assert_eq!(sorted(&four_times), sorted(&l_shape()));
+    }
+
+    #[test]
+    fn flips_within_the_bounding_box() {
+        assert_eq!(sorted(&flip_horizontal(&l_shape())), vec![(10, 22), (11, 20), (11, 21), (11, 22)]);
+        assert_eq!(sorted(&flip_vertical(&l_shape())), vec![(10, 20), (10, 21), (10, 22), (11, 20)]);
+        assert_eq!(sorted(&flip_vertical(&flip_vertical(&l_shape()))), sorted(&l_shape()));
+    }
+}
@@ -0,0 +1,218 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::coord::Coord;
+use crate::rule::Rule;
+use crate::universe::Universe;
+
+// A pattern that repeats itself, possibly moved, every `period` generations from generation `start` on.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Periodicity {
+    pub start: u64,
+    pub period: u64,
+    pub dx: i32,
+    pub dy: i32,
+    pub population: usize,
+}
+
+impl Periodicity {
+    pub fn kind(&self) -> &str {
+        if self.population == 0 {
+            "dies out"
+        } else if self.dx != 0 || self.dy != 0 {
+            "spaceship"
+        } else if self.period == 1 {
+            "still life"
+        } else {
+            "oscillator"
+        }
+    }
+
+    // Speed in c notation, e.g. "c/4 diagonal", "2c/5 orthogonal" or "(2,1)c/6", None for patterns that don't move.
+    pub fn speed(&self) -> Option<String> {
+        let (dx, dy) = (self.dx.unsigned_abs() as u64, self.dy.unsigned_abs() as u64);
+        if dx == 0 && dy == 0 { return None }
+        let distance = dx.max(dy);
+        let divisor = gcd(gcd(dx, dy), self.period);
+        let period = self.period / divisor;
+        let fraction = |cells: u64| if cells == 1 { format!("c/{}", period) } else { format!("{}c/{}", cells, period) };
+
+        Some(if dx == 0 || dy == 0 {
+            format!("{} orthogonal", fraction(distance / divisor))
+        } else if dx == dy {
+            format!("{} diagonal", fraction(distance / divisor))
+        } else {
+            format!("({},{})c/{}", dx.max(dy) / divisor, dx.min(dy) / divisor, period)
+        })
+    }
+
+    pub fn describe(&self) -> String {
+        match self.speed() {
+            Some(speed) => format!("p{} {} {}, dx {} dy {}", self.period, speed, self.kind(), self.dx, self.dy),
+            None if self.population == 0 => format!("dies out at generation {}", self.start),
+            None => format!("p{} {}", self.period, self.kind()),
+        }
+    }
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+// Hash of the cells relative to their bounding box, so that translated copies of a state hash the same.
+// Cell hashes are summed, which doesn't depend on the order the tiles are visited in and needs no sorting.
+pub fn state_hash(universe: &Universe) -> (u64, Coord) {
+    let min = match universe.bounding_box() { Some((min, _)) => min, None => return (0, Coord::new(0, 0)) };
+    let hash = universe.iter().fold(0u64, |hash, c| {
+        let key = ((c.x - min.x) as u32 as u64) << 32 | (c.y - min.y) as u32 as u64;
+        hash.wrapping_add(mix(key))
+    });
+    (hash, min)
+}
+
+// splitmix64 finalizer, https://prng.di.unimi.it/splitmix64.c
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
+
+// Cells kept by a period detector to compare states with the same hash, about 80 MB.
+const MAX_STORED_CELLS: usize = 10_000_000;
+
+// A past generation, with its cells relative to its bounding box in row order.
+struct Seen {
+    generation: u64,
+    min: Coord,
+    cells: Vec<(i32, i32)>,
+}
+
+// Remembers past generations by hash to notice when a state comes back.
+pub struct PeriodDetector {
+    seen: HashMap<(u64, usize), Seen>,
+    // Keys of the remembered generations, oldest first, with the generation each was stored for.
+    order: VecDeque<((u64, usize), u64)>,
+    capacity: usize,
+    stored_cells: usize,
+}
+
+impl PeriodDetector {
+    pub fn new(capacity: usize) -> PeriodDetector {
+        PeriodDetector { seen: HashMap::new(), order: VecDeque::new(), capacity, stored_cells: 0 }
+    }
+
+    pub fn clear(&mut self) {
+        self.seen.clear();
+        self.order.clear();
+        self.stored_cells = 0;
+    }
+
+    // Records the state of the generation, returning the periodicity if the same state was seen before. States with
+    // the same hash are compared cell by cell, so that a hash collision isn't mistaken for a period.
+    // Only the last capacity generations are remembered, fewer if they hold more than MAX_STORED_CELLS cells, so
+    // memory stays bounded for growing patterns while periods up to capacity are still found.
+    pub fn observe(&mut self, generation: u64, universe: &Universe) -> Option<Periodicity> {
+        let (hash, min) = state_hash(universe);
+        let key = (hash, universe.len());
+        let mut cells: Vec<(i32, i32)> = universe.iter().map(|c| (c.y - min.y, c.x - min.x)).collect();
+        cells.sort_unstable();
+        self.stored_cells += cells.len();
+        self.order.push_back((key, generation));
+        // Later occurrences replace earlier ones, so a pattern that keeps repeating reports its shortest period.
+        let periodicity = match self.seen.insert(key, Seen { generation, min, cells }) {
+            Some(start) => {
+                self.stored_cells -= start.cells.len();
+                (start.generation < generation && start.cells == self.seen[&key].cells).then(|| Periodicity {
+                    start: start.generation,
+                    period: generation - start.generation,
+                    dx: min.x - start.min.x,
+                    dy: min.y - start.min.y,
+                    population: universe.len(),
+                })
+            },
+            None => None
+        };
+
+        while self.order.len() > self.capacity || (self.stored_cells > MAX_STORED_CELLS && self.order.len() > 1) {
+            let (key, generation) = match self.order.pop_front() { Some(oldest) => oldest, None => break };
+            // The key may have been stored again for a later generation, which is still remembered.
+            if self.seen.get(&key).is_some_and(|seen| seen.generation == generation) {
+                self.stored_cells -= self.seen.remove(&key).map_or(0, |seen| seen.cells.len());
+            }
+        }
+        periodicity
+    }
+}
+
+// Runs the pattern until a state repeats, giving up after max_generations.
+pub fn detect(universe: &Universe, rule: &Rule, max_generations: u64) -> Option<Periodicity> {
+    let mut detector = PeriodDetector::new(max_generations as usize + 1);
+    let mut universe = universe.clone();
+    for generation in 0..=max_generations {
+        if let Some(periodicity) = detector.observe(generation, &universe) { return Some(periodicity) }
+        universe = universe.step(rule);
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rle;
+
+    fn detect_rle(pattern: &str) -> Periodicity {
+        let universe: Universe = rle::parse_pattern(pattern).unwrap().into_iter().collect();
+        detect(&universe, &Rule::life(), 100).unwrap()
+    }
+
+    fn row(length: i32) -> Universe {
+        (0..length).map(|x| Coord::new(x * 2, 0)).collect()
+    }
+
+    #[test]
+    fn finds_oscillators() {
+        let blinker = detect_rle("3o!");
+        assert_eq!((blinker.start, blinker.period, blinker.dx, blinker.dy), (0, 2, 0, 0));
+        assert_eq!(blinker.describe(), "p2 oscillator");
+        assert_eq!(detect_rle("2o$2o!").describe(), "p1 still life");
+    }
+
+    #[test]
+    fn finds_spaceships() {
+        let glider = detect_rle("bo$2bo$3o!");
+        assert_eq!((glider.period, glider.dx, glider.dy), (4, 1, 1));
+        assert_eq!(glider.speed().as_deref(), Some("c/4 diagonal"));
+        let lwss = detect_rle("bo2bo$o4b$o3bo$4o!");
+        assert_eq!((lwss.period, lwss.dx.abs(), lwss.dy), (4, 2, 0));
+        assert_eq!(lwss.speed().as_deref(), Some("c/2 orthogonal"));
+    }
+
+    #[test]
+    fn finds_patterns_that_die() {
+        let pair = detect_rle("2o!");
+        assert_eq!((pair.start, pair.population), (1, 0));
+        assert_eq!(pair.describe(), "dies out at generation 1");
+    }
+
+    #[test]
+    fn remembers_the_last_generations_up_to_the_capacity() {
+        let mut detector = PeriodDetector::new(3);
+        for (generation, length) in [1, 2, 3, 4].into_iter().enumerate() {
+            assert!(detector.observe(generation as u64, &row(length)).is_none());
+        }
+        // The first row has been forgotten, the third is still remembered.
+        assert!(detector.observe(4, &row(1)).is_none());
+        let periodicity = detector.observe(5, &row(3)).unwrap();
+        assert_eq!((periodicity.start, periodicity.period), (2, 3));
+    }
+
+    #[test]
+    fn tells_apart_states_with_the_same_hash() {
+        let mut detector = PeriodDetector::new(10);
+        let universe = row(3);
+        let (hash, min) = state_hash(&universe);
+        // A different state stored under the same key is not a repeat.
+        detector.seen.insert((hash, 3), Seen { generation: 0, min, cells: vec![(0, 0), (0, 1), (0, 2)] });
+        assert!(detector.observe(1, &universe).is_none());
+        assert!(detector.observe(2, &universe).is_some());
+    }
+}
@@ -0,0 +1,99 @@
+use std::collections::VecDeque;
+use std::fs;
+
+use crate::universe::Universe;
+
+// Statistics of one generation, births and deaths counted from the generation before.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Sample {
+    pub generation: u64,
+    pub population: usize,
+    pub births: usize,
+    pub deaths: usize,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl Sample {
+    pub fn new(generation: u64, universe: &Universe, births: usize, deaths: usize) -> Sample {
+        let (width, height) = match universe.bounding_box() {
+            Some((min, max)) => (max.x - min.x + 1, max.y - min.y + 1),
+            None => (0, 0)
+        };
+        Sample { generation, population: universe.len(), births, deaths, width, height }
+    }
+
+    // Sample of the next generation, counting the cells that changed between the two.
+    pub fn between(generation: u64, previous: &Universe, next: &Universe) -> Sample {
+        let births = next.iter().filter(|c| !previous.contains(c)).count();
+        let deaths = previous.iter().filter(|c| !next.contains(c)).count();
+        Sample::new(generation, next, births, deaths)
+    }
+}
+
+// Samples of the most recent generations, dropping the oldest beyond capacity.
+pub struct PopulationHistory {
+    pub samples: VecDeque<Sample>,
+    capacity: usize,
+}
+
+impl PopulationHistory {
+    pub fn new(capacity: usize) -> PopulationHistory {
+        PopulationHistory { samples: VecDeque::new(), capacity }
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    pub fn record(&mut self, sample: Sample) {
+        self.samples.push_back(sample);
+        while self.samples.len() > self.capacity {
+            self.samples.pop_front();
+        }
+    }
+
+    pub fn to_csv(&self) -> String {
+        to_csv(self.samples.iter())
+    }
+}
+
+pub fn to_csv<'a>(samples: impl Iterator<Item = &'a Sample>) -> String {
+    let mut csv = String::from("generation,population,births,deaths,width,height\n");
+    for s in samples {
+        csv.push_str(&format!("{},{},{},{},{},{}\n", s.generation, s.population, s.births, s.deaths, s.width, s.height));
+    }
+    csv
+}
+
+pub fn save_csv(path: &str, history: &PopulationHistory) -> Result<(), String> {
+    fs::write(path, history.to_csv()).map_err(|e| format!("could not write {}: {}", path, e))?;
+    println!("saved {} generations to {}", history.samples.len(), path);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coord::Coord;
+    use crate::rule::Rule;
+
+    #[test]
+    fn counts_births_and_deaths_between_generations() {
+        let blinker: Universe = (0..3).map(|x| Coord::new(x, 0)).collect();
+        let next = blinker.step(&Rule::life());
+        assert_eq!(Sample::between(1, &blinker, &next), Sample { generation: 1, population: 3, births: 2, deaths: 2, width: 1, height: 3 });
+        assert_eq!(Sample::new(0, &Universe::new(), 0, 0), Sample { generation: 0, population: 0, births: 0, deaths: 0, width: 0, height: 0 });
+    }
+
+    #[test]
+    fn writes_the_kept_samples_as_csv() {
+        let mut history = PopulationHistory::new(2);
+        for generation in 0..3 {
+            history.record(Sample { generation, population: 10 + generation as usize, births: 1, deaths: 2, width: 3, height: 4 });
+        }
+        assert_eq!(history.to_csv(), "generation,population,births,deaths,width,height\n1,11,1,2,3,4\n2,12,1,2,3,4\n");
+        history.clear();
+        assert_eq!(history.to_csv(), "generation,population,births,deaths,width,height\n");
+    }
+}
@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+use crate::config::Config;
+use crate::coord::Coord;
+use crate::rle;
+use crate::rule::Rule;
+use crate::sat::{Lit, Outcome, Solver};
+use crate::state::State;
+use crate::universe::Universe;
+
+#[derive(Clone, Copy)]
+pub struct PredecessorOptions {
+    // Live cells of the parent may lie this many cells outside the target rectangle.
+    pub margin: i32,
+    // Also requires the cells around the target rectangle to be dead in the next generation, so that the parent
+    // becomes exactly the target rather than the target with something next to it.
+    pub exact: bool,
+    pub max_conflicts: u64,
+}
+
+pub enum PredecessorResult {
+    Found(Vec<Coord>),
+    // No parent exists within the margin. Without exact, the cells of the rectangle only depend on the cells at
+    // most one cell away, so this proves the target is a Garden of Eden.
+    NoPredecessor,
+    // The solver gave up after max_conflicts conflicts.
+    Unknown,
+}
+
+// Looks for a generation that evolves into the cells of the universe inside the rectangle between min and max.
+// Every cell of the parent region is a variable, and every constrained cell forbids each assignment of itself and
+// its neighbors that the rule would turn into the wrong state.
+pub fn search(universe: &Universe, min: Coord, max: Coord, rule: &Rule, options: &PredecessorOptions) -> PredecessorResult {
+    let margin = options.margin.max(1);
+    let mut solver = Solver::new();
+    let mut vars: HashMap<Coord, Lit> = HashMap::new();
+    for y in min.y - margin..=max.y + margin {
+        for x in min.x - margin..=max.x + margin {
+            vars.insert(Coord::new(x, y), solver.new_var());
+        }
+    }
+
+    let border = if options.exact { margin + 1 } else { 0 };
+    for y in min.y - border..=max.y + border {
+        for x in min.x - border..=max.x + border {
+            let coord = Coord::new(x, y);
+            let inside = x >= min.x && x <= max.x && y >= min.y && y <= max.y;
+            let live = inside && universe.contains(&coord);
+            constrain(&mut solver, &vars, coord, live, rule);
+        }
+    }
+
+    match solver.solve(options.max_conflicts) {
+        Outcome::Satisfiable(model) => {
+            PredecessorResult::Found(vars.iter().filter(|(_, lit)| model[**lit as usize]).map(|(c, _)| *c).collect())
+        },
+        Outcome::Unsatisfiable => PredecessorResult::NoPredecessor,
+        Outcome::Unknown => PredecessorResult::Unknown,
+    }
+}
+
+// Requires the cell to be live or dead in the next generation. Cells outside the parent region are dead, so only
+// the assignments of the cells inside it are enumerated.
+fn constrain(solver: &mut Solver, vars: &HashMap<Coord, Lit>, coord: Coord, live: bool, rule: &Rule) {
+    let center = vars.get(&coord).copied();
+    let neighbors: Vec<Lit> = coord.neighbors().iter().filter_map(|c| vars.get(c).copied()).collect();
+    let lits: Vec<Lit> = center.into_iter().chain(neighbors.iter().copied()).collect();
+
+    for assignment in 0u32..1 << lits.len() {
+        let on = |i: usize| assignment & (1 << i) != 0;
+        let alive = center.is_some() && on(0);
+        let offset = center.is_some() as usize;
+        let count = (0..neighbors.len()).filter(|i| on(i + offset)).count() as u8;
+        if rule.next_state(alive, count) == live { continue }
+        let clause: Vec<Lit> = lits.iter().enumerate().map(|(i, lit)| if on(i) { -lit } else { *lit }).collect();
+        solver.add_clause(&clause);
+    }
+}
+
+// A search running on its own thread, so that the window keeps responding while the solver works.
+pub struct PendingSearch {
+    receiver: Receiver<PredecessorResult>,
+    options: PredecessorOptions,
+}
+
+// Starts searching for a predecessor of the selection, the result is reported by update once there is one.
+pub fn find_for_selection(state: &mut State, config: &Config) -> Result<String, String> {
+    if state.predecessor_search.is_some() { return Err("already searching for a predecessor".to_string()) }
+    let selection = state.selection.ok_or("select the target pattern first")?;
+    let (min, max) = (selection.min(), selection.max());
+    let target: Universe = state.cell_coords.cells_in_rect(min, max).into_iter().collect();
+    let (rule, options) = (state.rule, config.predecessor_options);
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let _ = sender.send(search(&target, min, max, &rule, &options));
+    });
+    state.predecessor_search = Some(PendingSearch { receiver, options });
+    Ok("searching for a predecessor of the selection".to_string())
+}
+
+// Prints the result of the running search to the console and stdout once it is done.
+pub fn update(state: &mut State) {
+    let pending = match &state.predecessor_search { Some(p) => p, None => return };
+    let result = match pending.receiver.try_recv() {
+        Ok(result) => result,
+        Err(TryRecvError::Empty) => return,
+        Err(TryRecvError::Disconnected) => {
+            state.predecessor_search = None;
+            return println!("the predecessor search stopped without a result")
+        }
+    };
+    let options = pending.options;
+    state.predecessor_search = None;
+    let message = match report(state, &options, result) {
+        Ok(message) => message,
+        Err(e) => format!("could not find a predecessor: {}", e),
+    };
+    println!("{}", message);
+    for line in message.lines() { state.console.print(line.to_string()) }
+}
+
+// Copies a predecessor that was found to the clipboard as RLE.
+fn report(state: &mut State, options: &PredecessorOptions, result: PredecessorResult) -> Result<String, String> {
+    match result {
+        PredecessorResult::Found(cells) => {
+            let encoded = rle::encode_pattern(&cells, &state.rule);
+            state.clipboard = Some(encoded.clone());
+            Ok(format!("found a predecessor with {} cells, copied to the clipboard:\n{}", cells.len(), encoded))
+        },
+        PredecessorResult::NoPredecessor if options.exact => {
+            Ok(format!("no predecessor within {} cells that evolves into exactly the selection", options.margin))
+        },
+        PredecessorResult::NoPredecessor => Ok("no predecessor: the selection is a Garden of Eden".to_string()),
+        PredecessorResult::Unknown => Err(format!("gave up after {} conflicts", options.max_conflicts)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(margin: i32, exact: bool) -> PredecessorOptions {
+        PredecessorOptions { margin, exact, max_conflicts: 100_000 }
+    }
+
+    fn sorted(cells: impl Iterator<Item = Coord>) -> Vec<(i32, i32)> {
+        let mut cells: Vec<(i32, i32)> = cells.map(|c| (c.x, c.y)).collect();
+        cells.sort();
+        cells
+    }
+
+    #[test]
+    fn finds_a_parent_of_a_blinker() {
+        let rule = Rule::life();
+        let blinker: Universe = (0..3).map(|y| Coord::new(1, y)).collect();
+        let (min, max) = (Coord::new(0, 0), Coord::new(2, 2));
+        for exact in [false, true] {
+            let parent = match search(&blinker, min, max, &rule, &options(1, exact)) {
+                PredecessorResult::Found(cells) => cells.into_iter().collect::<Universe>(),
+                _ => panic!("the blinker has a predecessor"),
+            };
+            let child = parent.step(&rule);
+            assert_eq!(sorted(child.cells_in_rect(min, max).into_iter()), sorted(blinker.iter()));
+            if exact { assert_eq!(sorted(child.iter()), sorted(blinker.iter())) }
+        }
+    }
+
+    #[test]
+    fn finds_no_parent_that_becomes_exactly_an_impossible_target() {
+        let rule = Rule::life();
+        // Even a lone cell has one, three cells around it that all die.
+        let origin = Coord::new(0, 0);
+        let cell: Universe = [origin].into_iter().collect();
+        assert!(matches!(search(&cell, origin, origin, &rule, &options(1, true)), PredecessorResult::Found(cells) if cells.len() == 3));
+        // One of the four 4 by 4 targets, up to symmetry, that nothing within one cell turns into exactly.
+        let target: Universe = [(0, 0), (0, 3), (1, 1), (2, 2), (3, 0)].iter().map(|(x, y)| Coord::new(*x, *y)).collect();
+        let (min, max) = (Coord::new(0, 0), Coord::new(3, 3));
+        assert!(matches!(search(&target, min, max, &rule, &options(1, true)), PredecessorResult::NoPredecessor));
+        assert!(matches!(search(&target, min, max, &rule, &options(1, false)), PredecessorResult::Found(_)));
+    }
+
+    #[test]
+    fn reports_the_search_once_it_is_done() {
+        let mut state = State::new();
+        let config = Config::new();
+        assert!(find_for_selection(&mut state, &config).is_err());
+        let (sender, receiver) = mpsc::channel();
+        state.predecessor_search = Some(PendingSearch { receiver, options: options(1, false) });
+        update(&mut state);
+        assert!(state.predecessor_search.is_some());
+        sender.send(PredecessorResult::Found(vec![Coord::new(0, 0), Coord::new(1, 0)])).unwrap();
+        update(&mut state);
+        assert!(state.predecessor_search.is_none());
+        assert_eq!(state.clipboard.as_deref(), Some("x = 2, y = 1, rule = B3/S23\n2o!\n"));
+    }
+}
@@ -1,28 +1,96 @@
+use std::collections::HashMap;
+
 use sdl2::rect::{Rect, Point};
-use sdl2::{render::Canvas, pixels::Color};
-use sdl2::video::Window;
+use sdl2::{render::{Canvas, Texture, TextureCreator}, pixels::{Color, PixelFormatEnum}};
+use sdl2::video::{FullscreenType, Window, WindowContext};
 
+use crate::coloring::{self, RenderMode};
 use crate::config::Config;
+use crate::drawing;
+use crate::library;
+use crate::minimap::{self, Minimap};
+use crate::population::Sample;
 use crate::coord::Coord;
-use crate::state::State;
+use crate::stamp::StampMode;
+use crate::state::{State, Tool};
+use crate::universe::{Tile, Universe, TILE_SIZE};
 use crate::utils;
 
-pub fn render(canvas: &mut Canvas<Window>, state: &State, config: &Config) -> () {
+// Streaming textures kept from frame to frame, recreated only when the size they are needed at changes.
+pub struct Textures<'a> {
+    creator: &'a TextureCreator<WindowContext>,
+    density: Option<Texture<'a>>,
+    dense_tiles: Option<Texture<'a>>,
+    heat: Option<Texture<'a>>,
+}
+
+impl<'a> Textures<'a> {
+    pub fn new(creator: &'a TextureCreator<WindowContext>) -> Textures<'a> {
+        Textures { creator, density: None, dense_tiles: None, heat: None }
+    }
+}
+
+// The texture in the slot, replaced by a new one unless it already has the size.
+fn streaming_texture<'t, 'a>(creator: &'a TextureCreator<WindowContext>, slot: &'t mut Option<Texture<'a>>, width: u32, height: u32) -> Result<&'t mut Texture<'a>, String> {
+    if slot.as_ref().is_none_or(|t| (t.query().width, t.query().height) != (width, height)) {
+        let mut texture = creator
+            .create_texture_streaming(PixelFormatEnum::RGBA32, width, height)
+            .map_err(|e| e.to_string())?;
+        texture.set_blend_mode(sdl2::render::BlendMode::Blend);
+        *slot = Some(texture);
+    }
+    slot.as_mut().ok_or("no texture".to_string())
+}
+
+pub fn render(canvas: &mut Canvas<Window>, textures: &mut Textures, state: &State, config: &Config) -> () {
     let color = config.background_color;
     canvas.set_draw_color(color);
     canvas.clear();
 
     render_hover(canvas, &state, &config);
-    render_state(canvas, &state, &config);
+    if state.render_mode == RenderMode::Heat { let _ = render_heat(canvas, textures, state, config); }
+    render_history(canvas, state, config);
+    render_state(canvas, textures, &state, &config);
+    render_stroke(canvas, state, config);
+    render_stamp(canvas, state, config);
     render_grid(canvas, &state, &config);
+    render_selection(canvas, state, config);
+    render_minimap(canvas, state, config);
+    if state.graph_visible { let _ = render_graph(canvas, state, config); }
     render_fps(canvas, &state, &config);
+    if state.hud_visible { let _ = render_hud(canvas, state, config); }
 
     if state.t < config.intro_duration_ms { let _ = render_intro(canvas, &state, &config); }
     if state.paused { render_paused(canvas, &state, &config) }
+    if state.help_visible { let _ = render_help(canvas, state, config); }
+    if state.library.open { let _ = render_library(canvas, state, config); }
+    if state.console.open { let _ = render_console(canvas, state, config); }
 
     canvas.present();
 }
 
+// Applies fullscreen changes and resizes the viewport to the drawable size of the window,
+// keeping the cell at the center of the window in place.
+pub fn update_viewport(canvas: &mut Canvas<Window>, state: &mut State, config: &mut Config) -> Result<(), String> {
+    state.viewport_changed = false;
+
+    let fullscreen = if state.fullscreen { FullscreenType::Desktop } else { FullscreenType::Off };
+    if canvas.window().fullscreen_state() != fullscreen {
+        canvas.window_mut().set_fullscreen(fullscreen)?;
+    }
+
+    let (width, height) = canvas.output_size()?;
+    let (window_width, _) = canvas.window().size();
+    config.set_pixel_density(width as f32 / window_width.max(1) as f32);
+
+    state.camera_x += (config.window_width - width as f32) / 2.0;
+    state.camera_y += (config.window_height - height as f32) / 2.0;
+    config.window_width = width as f32;
+    config.window_height = height as f32;
+
+    Ok(())
+}
+
 fn render_intro(canvas: &mut Canvas<Window>, state: &State, config: &Config) -> Result<(), String> {
     let message: String = "Game of Rust".to_string();
     let texture_creator = canvas.texture_creator();
@@ -70,6 +138,10 @@ fn render_message_center(message: &String, canvas: &mut Canvas<Window>, _state:
 }
 
 fn render_message(message: &String, x: i32, y: i32, canvas: &mut Canvas<Window>, _state: &State, config: &Config) -> Result<(), String> {
+    render_text(message, x, y, config.char_width, config.char_height, canvas, config)
+}
+
+fn render_text(message: &str, x: i32, y: i32, char_width: f32, char_height: f32, canvas: &mut Canvas<Window>, config: &Config) -> Result<(), String> {
     let texture_creator = canvas.texture_creator();
     let surface = config.font.as_ref().unwrap()
         .render(message)
@@ -78,8 +150,8 @@ fn render_message(message: &String, x: i32, y: i32, canvas: &mut Canvas<Window>,
     let texture = texture_creator
         .create_texture_from_surface(&surface)
         .map_err(|e| e.to_string())?;
-    let message_width = message.len() as f32 * config.char_width;
-    let message_height = config.char_height;
+    let message_width = message.len() as f32 * char_width;
+    let message_height = char_height;
     let target = Rect::new(
         x,
         y,
@@ -91,6 +163,94 @@ fn render_message(message: &String, x: i32, y: i32, canvas: &mut Canvas<Window>,
     Ok(())
 }
 
+// Lists the current bindings in as many columns as needed to fit the window.
+fn render_help(canvas: &mut Canvas<Window>, _state: &State, config: &Config) -> Result<(), String> {
+    let lines = config.bindings.describe();
+    let margin = config.small_char_height;
+    let rows = (((config.window_height - 2.0 * margin) / config.small_char_height) as usize).max(1);
+    let longest = lines.iter().map(|l| l.len()).max().unwrap_or(0);
+    let column_width = (longest + 2) as f32 * config.small_char_width;
+
+    canvas.set_blend_mode(sdl2::render::BlendMode::Blend);
+    canvas.set_draw_color(config.help_background_color);
+    canvas.fill_rect(Rect::new(0, 0, config.window_width as u32, config.window_height as u32))?;
+
+    for (i, line) in lines.iter().enumerate() {
+        let x = margin + (i / rows) as f32 * column_width;
+        let y = margin + (i % rows) as f32 * config.small_char_height;
+        render_text(line, x as i32, y as i32, config.small_char_width, config.small_char_height, canvas, config)?;
+    }
+
+    Ok(())
+}
+
+// Pattern list on the left side of the window with the running preview and details of the selected pattern below it.
+fn render_library(canvas: &mut Canvas<Window>, state: &State, config: &Config) -> Result<(), String> {
+    let layout = library::layout(state, config);
+    let (char_width, char_height) = (config.small_char_width, config.small_char_height);
+    let max_chars = ((layout.width - 2.0 * layout.margin) / char_width) as usize;
+    let truncate = |text: &str| text.chars().take(max_chars).collect::<String>();
+
+    canvas.set_blend_mode(sdl2::render::BlendMode::Blend);
+    canvas.set_draw_color(config.library_background_color);
+    canvas.fill_rect(Rect::new(0, 0, layout.width as u32, config.window_height as u32))?;
+
+    let patterns = &state.library.patterns;
+    if patterns.is_empty() {
+        return render_text("no patterns found", layout.margin as i32, layout.margin as i32, char_width, char_height, canvas, config)
+    }
+    for (row, pattern) in patterns.iter().enumerate().skip(layout.first_row).take(layout.rows) {
+        let y = layout.margin + (row - layout.first_row) as f32 * layout.row_height;
+        if row == state.library.selected {
+            canvas.set_draw_color(config.library_highlight_color);
+            canvas.fill_rect(Rect::new(0, y as i32, layout.width as u32, layout.row_height as u32))?;
+        }
+        render_text(&truncate(&pattern.name), layout.margin as i32, y as i32, char_width, char_height, canvas, config)?;
+    }
+
+    let size = layout.width - 2.0 * layout.margin;
+    let frame = Rect::new(layout.margin as i32, layout.preview_y as i32, size as u32, size as u32);
+    if let Some(view) = library::preview_view(state, config) { render_thumbnail(canvas, &state.library.preview, &view, config.cell_color) }
+    canvas.set_draw_color(config.minimap_border_color);
+    canvas.draw_rect(frame)?;
+
+    let pattern = match state.library.selected_pattern() { Some(p) => p, None => return Ok(()) };
+    let info = [
+        pattern.name.clone(),
+        pattern.author.clone().unwrap_or("unknown author".to_string()),
+        format!("{} x {}", pattern.width, pattern.height),
+        pattern.path.clone(),
+    ];
+    for (i, line) in info.iter().enumerate() {
+        let y = layout.info_y + i as f32 * layout.row_height;
+        render_text(&truncate(line), layout.margin as i32, y as i32, char_width, char_height, canvas, config)?;
+    }
+
+    Ok(())
+}
+
+// Drops down from the top of the window showing the latest output lines above the input line.
+fn render_console(canvas: &mut Canvas<Window>, state: &State, config: &Config) -> Result<(), String> {
+    let margin = config.small_char_height / 2.0;
+    let height = (config.console_lines + 1) as f32 * config.small_char_height + 2.0 * margin;
+
+    canvas.set_blend_mode(sdl2::render::BlendMode::Blend);
+    canvas.set_draw_color(config.console_background_color);
+    canvas.fill_rect(Rect::new(0, 0, config.window_width as u32, height as u32))?;
+
+    let output = &state.console.output;
+    let first = output.len().saturating_sub(config.console_lines);
+    for (i, line) in output[first..].iter().enumerate() {
+        if line.is_empty() { continue }
+        let y = margin + i as f32 * config.small_char_height;
+        render_text(line, margin as i32, y as i32, config.small_char_width, config.small_char_height, canvas, config)?;
+    }
+
+    let prompt = format!("> {}_", state.console.input);
+    let y = margin + config.console_lines as f32 * config.small_char_height;
+    render_text(&prompt, margin as i32, y as i32, config.small_char_width, config.small_char_height, canvas, config)
+}
+
 fn render_blur(canvas: &mut Canvas<Window>, _state: &State, config: &Config) {
     let mut color = config.background_color.clone();
     color.a = 50;
@@ -101,6 +261,8 @@ fn render_blur(canvas: &mut Canvas<Window>, _state: &State, config: &Config) {
 }
 
 fn render_grid(canvas: &mut Canvas<Window>, state: &State, config: &Config) {
+    if state.cell_width < 1.0 || state.cell_height < 1.0 { return }
+
     let mut color = config.grid_color.clone();
     color.a = (state.cell_height / config.max_cell_height * 255.0).round() as u8;
     canvas.set_draw_color(color);
@@ -125,24 +287,179 @@ fn render_grid(canvas: &mut Canvas<Window>, state: &State, config: &Config) {
     }
 }
 
-fn render_state(canvas: &mut Canvas<Window>, state: &State, config: &Config) {
-    let mut rects: Vec<Rect> = Vec::new();
-    for cell_coord in state.cell_coords.iter() {
-        let cell_wx = cell_coord.x as f32 * state.cell_width - state.camera_x;
-        let cell_wy = cell_coord.y as f32 * state.cell_height - state.camera_y;
-        if cell_wx > -state.cell_width && cell_wx < config.window_width && cell_wy > -state.cell_height && cell_wy < config.window_height {
-            rects.push(
-                Rect::new(
-                    cell_wx.ceil() as i32,
-                    cell_wy.ceil() as i32,
-                    state.cell_width.ceil() as u32,
-                    state.cell_height.ceil() as u32
-                )
-            );
+fn render_state(canvas: &mut Canvas<Window>, textures: &mut Textures, state: &State, config: &Config) {
+    if state.cell_width < 1.0 || state.cell_height < 1.0 {
+        let _ = render_density(canvas, textures, state, config);
+        return
+    }
+
+    let (min, max) = utils::visible_cell_bounds(state, config);
+    let mut rects: HashMap<Color, Vec<Rect>> = HashMap::new();
+    let mut dense_tiles: Vec<(Coord, &Tile)> = Vec::new();
+    for (tile_coord, tile) in state.cell_coords.tiles_in_rect(min, max) {
+        if tile.population >= config.dense_tile_population {
+            dense_tiles.push((tile_coord, tile));
+            continue
+        }
+        for cell_coord in tile.cells(tile_coord) {
+            let cell_wx = cell_coord.x as f32 * state.cell_width - state.camera_x;
+            let cell_wy = cell_coord.y as f32 * state.cell_height - state.camera_y;
+            if cell_wx > -state.cell_width && cell_wx < config.window_width && cell_wy > -state.cell_height && cell_wy < config.window_height {
+                rects.entry(coloring::cell_color(state, config, &cell_coord)).or_default().push(
+                    Rect::new(
+                        cell_wx.ceil() as i32,
+                        cell_wy.ceil() as i32,
+                        state.cell_width.ceil() as u32,
+                        state.cell_height.ceil() as u32
+                    )
+                );
+            }
+        }
+    }
+    for (color, rects) in rects {
+        canvas.set_draw_color(color);
+        let _ =  canvas.fill_rects(&rects);
+    }
+    let _ = render_dense_tiles(canvas, textures, state, config, &dense_tiles);
+}
+
+// Dense tiles are drawn into a texture atlas, one pixel per cell, and blitted scaled up instead of as individual rects.
+fn render_dense_tiles(canvas: &mut Canvas<Window>, textures: &mut Textures, state: &State, config: &Config, tiles: &[(Coord, &Tile)]) -> Result<(), String> {
+    if tiles.is_empty() { return Ok(()) }
+
+    let size = TILE_SIZE as usize;
+    // The atlas is square with a power of two tiles on each side, so it only has to be recreated when the number
+    // of dense tiles crosses a power of four.
+    let columns = ((tiles.len() as f32).sqrt().ceil() as usize).next_power_of_two();
+    let rows = tiles.len().div_ceil(columns);
+    let pitch = columns * size * 4;
+    let mut pixels = vec![0u8; pitch * rows * size];
+    for (i, (tile_coord, tile)) in tiles.iter().enumerate() {
+        let (slot_x, slot_y) = ((i % columns) * size, (i / columns) * size);
+        for y in 0..size {
+            for x in 0..size {
+                if !tile.is_live(x as i32, y as i32) { continue }
+                let cell_coord = Coord::new(tile_coord.x * TILE_SIZE + x as i32, tile_coord.y * TILE_SIZE + y as i32);
+                let color = coloring::cell_color(state, config, &cell_coord);
+                let offset = (slot_y + y) * pitch + (slot_x + x) * 4;
+                pixels[offset..offset + 4].copy_from_slice(&[color.r, color.g, color.b, color.a]);
+            }
+        }
+    }
+
+    let side = (columns * size) as u32;
+    let texture = streaming_texture(textures.creator, &mut textures.dense_tiles, side, side)?;
+    let used = Rect::new(0, 0, side, (rows * size) as u32);
+    texture.update(Some(used), &pixels, pitch).map_err(|e| e.to_string())?;
+
+    for (i, (tile_coord, _)) in tiles.iter().enumerate() {
+        let source = Rect::new(((i % columns) * size) as i32, ((i / columns) * size) as i32, size as u32, size as u32);
+        let x = (tile_coord.x * TILE_SIZE) as f32 * state.cell_width - state.camera_x;
+        let y = (tile_coord.y * TILE_SIZE) as f32 * state.cell_height - state.camera_y;
+        let target = Rect::new(
+            x.ceil() as i32,
+            y.ceil() as i32,
+            (TILE_SIZE as f32 * state.cell_width).ceil() as u32,
+            (TILE_SIZE as f32 * state.cell_height).ceil() as u32
+        );
+        canvas.copy(texture, Some(source), Some(target))?;
+    }
+
+    Ok(())
+}
+
+// Zoomed out below one pixel per cell, each pixel is shaded by the fraction of live cells it covers.
+// Tiles that fit inside a single pixel contribute their population without visiting their cells.
+fn render_density(canvas: &mut Canvas<Window>, textures: &mut Textures, state: &State, config: &Config) -> Result<(), String> {
+    let width = config.window_width as usize;
+    let height = config.window_height as usize;
+    let cells_per_pixel = (1.0 / state.cell_width) * (1.0 / state.cell_height);
+    let tile_fits_pixel = TILE_SIZE as f32 * state.cell_width <= 1.0 && TILE_SIZE as f32 * state.cell_height <= 1.0;
+
+    let (min, max) = utils::visible_cell_bounds(state, config);
+    let mut counts = vec![0u32; width * height];
+    let mut count = |x: f32, y: f32, population: u32| {
+        let px = (x * state.cell_width - state.camera_x).floor();
+        let py = (y * state.cell_height - state.camera_y).floor();
+        if px < 0.0 || py < 0.0 || px >= width as f32 || py >= height as f32 { return }
+        counts[py as usize * width + px as usize] += population;
+    };
+    for (tile_coord, tile) in state.cell_coords.tiles_in_rect(min, max) {
+        if tile_fits_pixel {
+            let center = (TILE_SIZE / 2) as f32;
+            count((tile_coord.x * TILE_SIZE) as f32 + center, (tile_coord.y * TILE_SIZE) as f32 + center, tile.population);
+        } else {
+            for cell_coord in tile.cells(tile_coord) {
+                count(cell_coord.x as f32, cell_coord.y as f32, 1);
+            }
         }
     }
-    canvas.set_draw_color(config.cell_color);
-    let _ =  canvas.fill_rects(&rects);
+
+    let color = config.cell_color;
+    let mut pixels = vec![0u8; width * height * 4];
+    for (i, count) in counts.iter().enumerate() {
+        if *count == 0 { continue }
+        let density = (*count as f32 / cells_per_pixel).min(1.0);
+        let alpha = config.density_min_alpha + (1.0 - config.density_min_alpha) * density;
+        pixels[i * 4..i * 4 + 4].copy_from_slice(&[color.r, color.g, color.b, (alpha * color.a as f32) as u8]);
+    }
+
+    let texture = streaming_texture(textures.creator, &mut textures.density, width as u32, height as u32)?;
+    texture.update(None, &pixels, width * 4).map_err(|e| e.to_string())?;
+    canvas.copy(texture, None, None)?;
+
+    Ok(())
+}
+
+fn render_heat(canvas: &mut Canvas<Window>, textures: &mut Textures, state: &State, config: &Config) -> Result<(), String> {
+    let (min, max) = utils::visible_cell_bounds(state, config);
+    let visible = state.heat.iter()
+        .filter(|(coord, _)| coord.x >= min.x && coord.x <= max.x && coord.y >= min.y && coord.y <= max.y);
+    if state.cell_width >= 1.0 && state.cell_height >= 1.0 {
+        canvas.set_blend_mode(sdl2::render::BlendMode::Blend);
+        for (coord, heat) in visible {
+            render_cell(canvas, state, coord, coloring::heat_color(config, *heat));
+        }
+        return Ok(())
+    }
+
+    // Zoomed out below one pixel per cell, each pixel shows the hottest cell it covers.
+    let width = config.window_width as usize;
+    let height = config.window_height as usize;
+    let mut hottest = vec![0u32; width * height];
+    for (coord, heat) in visible {
+        let px = (coord.x as f32 * state.cell_width - state.camera_x).floor();
+        let py = (coord.y as f32 * state.cell_height - state.camera_y).floor();
+        if px < 0.0 || py < 0.0 || px >= width as f32 || py >= height as f32 { continue }
+        let pixel = &mut hottest[py as usize * width + px as usize];
+        *pixel = (*pixel).max(*heat);
+    }
+
+    let mut pixels = vec![0u8; width * height * 4];
+    for (i, heat) in hottest.iter().enumerate() {
+        if *heat == 0 { continue }
+        let color = coloring::heat_color(config, *heat);
+        pixels[i * 4..i * 4 + 4].copy_from_slice(&[color.r, color.g, color.b, color.a]);
+    }
+
+    let texture = streaming_texture(textures.creator, &mut textures.heat, width as u32, height as u32)?;
+    texture.update(None, &pixels, width * 4).map_err(|e| e.to_string())?;
+    canvas.copy(texture, None, None)?;
+
+    Ok(())
+}
+
+fn render_history(canvas: &mut Canvas<Window>, state: &State, config: &Config) {
+    let history = match &state.history { Some(h) => h, None => return };
+    let (min, max) = utils::visible_cell_bounds(state, config);
+    let rects: Vec<Rect> = history.cells_in_rect(min, max).iter().map(|coord| {
+        let x = coord.x as f32 * state.cell_width - state.camera_x;
+        let y = coord.y as f32 * state.cell_height - state.camera_y;
+        Rect::new(x.ceil() as i32, y.ceil() as i32, state.cell_width.ceil() as u32, state.cell_height.ceil() as u32)
+    }).collect();
+    canvas.set_blend_mode(sdl2::render::BlendMode::Blend);
+    canvas.set_draw_color(config.history_color);
+    let _ = canvas.fill_rects(&rects);
 }
 
 fn render_hover(canvas: &mut Canvas<Window>, state: &State, config: &Config) {
@@ -156,6 +473,38 @@ fn render_hover(canvas: &mut Canvas<Window>, state: &State, config: &Config) {
     render_cell(canvas, state, &coord, config.hover_color);
 }
 
+fn render_stroke(canvas: &mut Canvas<Window>, state: &State, config: &Config) {
+    let stroke = match &state.stroke { Some(s) => s, None => return };
+    let color = if stroke.erase { config.background_color } else { config.hover_color };
+    for coord in drawing::stroke_shape(state.tool, stroke) {
+        render_cell(canvas, state, &coord, color);
+    }
+}
+
+// Translucent ghost of the stamp centered on the cursor, copy mode also outlines the area it replaces.
+fn render_stamp(canvas: &mut Canvas<Window>, state: &State, config: &Config) {
+    if state.tool != Tool::Stamp { return }
+    let stamp = match &state.stamp { Some(s) => s, None => return };
+    let at = utils::game_coord(state.cursor_x as f32, state.cursor_y as f32, state);
+
+    canvas.set_blend_mode(sdl2::render::BlendMode::Blend);
+    let (min, max) = utils::visible_cell_bounds(state, config);
+    for coord in stamp.cells_at(at) {
+        if coord.x < min.x || coord.x > max.x || coord.y < min.y || coord.y > max.y { continue }
+        render_cell(canvas, state, &coord, config.stamp_color);
+    }
+
+    if stamp.mode == StampMode::Copy {
+        let origin = stamp.origin(at);
+        let x = origin.x as f32 * state.cell_width - state.camera_x;
+        let y = origin.y as f32 * state.cell_height - state.camera_y;
+        let width = (stamp.width as f32 * state.cell_width).max(1.0);
+        let height = (stamp.height as f32 * state.cell_height).max(1.0);
+        canvas.set_draw_color(config.selection_color);
+        let _ = canvas.draw_rect(Rect::new(x.ceil() as i32, y.ceil() as i32, width as u32, height as u32));
+    }
+}
+
 fn render_cell(canvas: &mut Canvas<Window>, state: &State, coord: &Coord, color: Color) {
     let x = coord.x as f32 * state.cell_width - state.camera_x;
     let y = coord.y as f32 * state.cell_height - state.camera_y;
@@ -163,6 +512,144 @@ fn render_cell(canvas: &mut Canvas<Window>, state: &State, coord: &Coord, color:
     canvas.fill_rect(Rect::new(x.ceil() as i32, y.ceil() as i32, state.cell_width.ceil() as u32, state.cell_height.ceil() as u32)).expect("could not fill rect");
 }
 
+fn render_selection(canvas: &mut Canvas<Window>, state: &State, config: &Config) {
+    let selection = match state.selection { Some(s) => s, None => return };
+    let min = selection.min();
+    let x = min.x as f32 * state.cell_width - state.camera_x;
+    let y = min.y as f32 * state.cell_height - state.camera_y;
+    let rect = Rect::new(
+        x.ceil() as i32,
+        y.ceil() as i32,
+        (selection.width() as f32 * state.cell_width).ceil() as u32,
+        (selection.height() as f32 * state.cell_height).ceil() as u32
+    );
+
+    let mut color = config.selection_color;
+    color.a = 40;
+    canvas.set_blend_mode(sdl2::render::BlendMode::Blend);
+    canvas.set_draw_color(color);
+    canvas.fill_rect(rect).expect("could not fill rect");
+    canvas.set_draw_color(config.selection_color);
+    canvas.draw_rect(rect).expect("could not draw rect");
+}
+
+// One point per pixel of the view, tiles smaller than a pixel are marked without visiting their cells.
+// Zoomed in views of small patterns draw whole cells instead.
+fn render_thumbnail(canvas: &mut Canvas<Window>, universe: &Universe, view: &Minimap, color: Color) {
+    canvas.set_draw_color(color);
+    if view.scale >= 2.0 {
+        let size = view.scale.floor() as u32;
+        let rects: Vec<Rect> = universe.iter().map(|c| {
+            let (x, y) = view.to_window(c.x as f32, c.y as f32);
+            Rect::new(x as i32, y as i32, size, size)
+        }).collect();
+        let _ = canvas.fill_rects(rects.as_slice());
+        return
+    }
+
+    let (width, height) = (view.width as usize, view.height as usize);
+    let mut marked = vec![false; width * height];
+    let mut mark = |cell_x: f32, cell_y: f32| {
+        let (x, y) = view.to_window(cell_x, cell_y);
+        let (px, py) = ((x - view.x).floor(), (y - view.y).floor());
+        if px < 0.0 || py < 0.0 || px >= width as f32 || py >= height as f32 { return }
+        marked[py as usize * width + px as usize] = true;
+    };
+    let tile_fits_pixel = TILE_SIZE as f32 * view.scale <= 1.0;
+    let (min, max) = universe.bounding_box().unwrap_or((Coord::new(0, 0), Coord::new(0, 0)));
+    for (tile_coord, tile) in universe.tiles_in_rect(min, max) {
+        if tile_fits_pixel {
+            mark((tile_coord.x * TILE_SIZE) as f32, (tile_coord.y * TILE_SIZE) as f32);
+        } else {
+            for cell_coord in tile.cells(tile_coord) { mark(cell_coord.x as f32, cell_coord.y as f32) }
+        }
+    }
+    let points: Vec<Point> = marked.iter().enumerate()
+        .filter(|(_, marked)| **marked)
+        .map(|(i, _)| Point::new(view.x as i32 + (i % width) as i32, view.y as i32 + (i / width) as i32))
+        .collect();
+    let _ = canvas.draw_points(points.as_slice());
+}
+
+fn render_minimap(canvas: &mut Canvas<Window>, state: &State, config: &Config) {
+    let minimap = match minimap::layout(state, config) { Some(m) => m, None => return };
+    let frame = Rect::new(minimap.x as i32, minimap.y as i32, minimap.width as u32, minimap.height as u32);
+    canvas.set_blend_mode(sdl2::render::BlendMode::Blend);
+    canvas.set_draw_color(config.minimap_background_color);
+    canvas.fill_rect(frame).expect("could not fill rect");
+
+    render_thumbnail(canvas, &state.cell_coords, &minimap, config.cell_color);
+
+    let (x0, y0) = minimap.to_window(state.camera_x / state.cell_width, state.camera_y / state.cell_height);
+    let (x1, y1) = minimap.to_window(
+        (state.camera_x + config.window_width) / state.cell_width,
+        (state.camera_y + config.window_height) / state.cell_height
+    );
+    let (x0, y0) = (x0.max(minimap.x), y0.max(minimap.y));
+    let (x1, y1) = (x1.min(minimap.x + minimap.width), y1.min(minimap.y + minimap.height));
+    if x1 > x0 && y1 > y0 {
+        canvas.set_draw_color(config.minimap_viewport_color);
+        canvas.draw_rect(Rect::new(x0 as i32, y0 as i32, (x1 - x0).max(1.0) as u32, (y1 - y0).max(1.0) as u32)).expect("could not draw rect");
+    }
+
+    canvas.set_draw_color(config.minimap_border_color);
+    canvas.draw_rect(frame).expect("could not draw rect");
+}
+
+// Population, births and deaths of the latest generations in the top right corner, one generation per pixel column
+// with the newest on the right, scaled to the largest value shown.
+fn render_graph(canvas: &mut Canvas<Window>, state: &State, config: &Config) -> Result<(), String> {
+    let (width, height) = (config.graph_width, config.graph_height);
+    let x = config.window_width - width - config.graph_margin;
+    let y = config.graph_margin;
+    let frame = Rect::new(x as i32, y as i32, width as u32, height as u32);
+    canvas.set_blend_mode(sdl2::render::BlendMode::Blend);
+    canvas.set_draw_color(config.graph_background_color);
+    canvas.fill_rect(frame)?;
+
+    let samples = &state.population_history.samples;
+    let shown: Vec<&Sample> = samples.iter().skip(samples.len().saturating_sub(width as usize)).collect();
+    let max = shown.iter().map(|s| s.population.max(s.births).max(s.deaths)).max().unwrap_or(0).max(1);
+    let series = [
+        (config.graph_deaths_color, shown.iter().map(|s| s.deaths).collect::<Vec<_>>()),
+        (config.graph_births_color, shown.iter().map(|s| s.births).collect()),
+        (config.graph_population_color, shown.iter().map(|s| s.population).collect()),
+    ];
+    for (color, values) in series {
+        let points: Vec<Point> = values.iter().enumerate().map(|(i, value)| Point::new(
+            (x + width - values.len() as f32 + i as f32) as i32,
+            (y + height - 1.0 - *value as f32 / max as f32 * (height - 1.0)) as i32
+        )).collect();
+        canvas.set_draw_color(color);
+        canvas.draw_lines(points.as_slice())?;
+    }
+
+    canvas.set_draw_color(config.minimap_border_color);
+    canvas.draw_rect(frame)?;
+    let margin = config.small_char_width / 2.0;
+    render_text(&format!("max {}", max), (x + margin) as i32, (y + margin) as i32, config.small_char_width, config.small_char_height, canvas, config)
+}
+
+// Generation, population, the detected period, stabilization and emissions in the bottom left corner.
+fn render_hud(canvas: &mut Canvas<Window>, state: &State, config: &Config) -> Result<(), String> {
+    let mut lines = vec![format!("generation {}  population {}  {}", state.generation, state.cell_coords.len(), state.rule)];
+    if let Some(periodicity) = &state.periodicity { lines.push(periodicity.describe()) }
+    if let Some(stabilization) = &state.stabilization { lines.push(stabilization.describe()) }
+    if let Some(tracker) = &state.emission_tracker {
+        match tracker.period() {
+            Some(period) => lines.push(format!("emitted {}, period {}", tracker.emissions.len(), period)),
+            None => lines.push(format!("emitted {}", tracker.emissions.len())),
+        }
+    }
+
+    let margin = config.small_char_height / 2.0;
+    for (i, line) in lines.iter().rev().enumerate() {
+        let y = config.window_height - margin - (i + 1) as f32 * config.small_char_height;
+        render_text(line, margin as i32, y as i32, config.small_char_width, config.small_char_height, canvas, config)?;
+    }
+    Ok(())
+}
+
 fn render_fps(canvas: &mut Canvas<Window>, state: &State, _config: &Config) {
     let _ = canvas.window_mut().set_title(&format!("game-of-rust, FPS: {}", state.fps));
 }
@@ -3,58 +3,286 @@
 b	dead cell
 o	alive cell
 $	end of line
+.	state 0 in multi-state patterns
+A-X	states 1-24 in multi-state patterns
 */
 
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::fs;
 
 use crate::coord::Coord;
+use crate::pattern;
+use crate::rule::Rule;
 use crate::state::State;
 
+const MAX_LINE_LENGTH: usize = 70;
+// Longer runs are rejected, so that a malformed file can't ask for billions of cells or overflow the coordinates.
+const MAX_RUN_COUNT: i32 = 1 << 24;
+
 pub fn load_pattern(name: &str, state: &mut State, x_offset: i32, y_offset: i32) -> Result<(), String> {
-  let file = File::open(name).expect("could not open file");
-  let reader = BufReader::new(file);
-  let mut x: i32 = x_offset;
-  let mut y: i32 = y_offset;
-  let mut run_count_str: String = "0".to_owned();
-  
   println!("loading pattern: {}", name);
-  for l in reader.lines() {
-    let l = l.unwrap();
+  let contents = fs::read_to_string(name).map_err(|e| format!("could not open {}: {}", name, e))?;
+  for coord in parse_pattern(&contents)? {
+    state.insert_cell(Coord::new(coord.x + x_offset, coord.y + y_offset));
+  }
+  if let Some(rule) = parse_rule(&contents) { state.set_rule(rule) }
+
+  Ok(())
+}
+
+pub fn read_pattern(name: &str) -> Result<Vec<Coord>, String> {
+  let contents = fs::read_to_string(name).map_err(|e| format!("could not open {}: {}", name, e))?;
+  parse_pattern(&contents)
+}
+
+pub fn parse_pattern(contents: &str) -> Result<Vec<Coord>, String> {
+  let mut cells = Vec::new();
+  let mut x: i32 = 0;
+  let mut y: i32 = 0;
+  let mut run_count_str: String = "0".to_owned();
+  let mut header_seen = false;
+  // Multi-state cells only map onto live and dead cells in LifeHistory, where odd states are alive.
+  let life_history = rule_name(contents).is_some_and(|r| r.eq_ignore_ascii_case("LifeHistory"));
+
+  'lines: for l in contents.lines() {
     for c in l.chars() {
       match c {
-        '#' | 'x' => {
+        '#' => {
+          break;
+        },
+        'x' if !header_seen => {
+          header_seen = true;
           break;
         },
         '0'..='9' => {
           run_count_str.push(c);
         },
         'o' => {
-          let run_count = if run_count_str == "0" { 1 } else { run_count_str.parse::<i32>().unwrap() };
-          for _ in 0..run_count {
-            state.cell_coords.insert(Coord::new(x, y));
-            x += 1;
-          }
+          let count = run_count(&run_count_str)?;
+          cells.extend((0..count).map(|i| Coord::new(x + i, y)));
+          x = advance(x, count)?;
           run_count_str = "0".to_owned();
         },
-        'b' => {
-          let run_count = if run_count_str == "0" { 1 } else { run_count_str.parse::<i32>().unwrap() };
-          x += run_count;
+        'b' | '.' => {
+          x = advance(x, run_count(&run_count_str)?)?;
           run_count_str = "0".to_owned();
         },
+        'A'..='X' => {
+          if !life_history {
+            return Err(format!("multi-state cell {} in a pattern with rule {}", c, rule_name(contents).unwrap_or("B3/S23")))
+          }
+          let alive = (c as u8 - b'A').is_multiple_of(2);
+          let count = run_count(&run_count_str)?;
+          if alive { cells.extend((0..count).map(|i| Coord::new(x + i, y))) }
+          x = advance(x, count)?;
+          run_count_str = "0".to_owned();
+        },
+        // prefixes of states 25 and up, which LifeHistory doesn't have
+        'p'..='y' => {
+          return Err(format!("multi-state cell with prefix {} is not supported", c))
+        },
         '$' => {
-          let run_count = if run_count_str == "0" { 1 } else { run_count_str.parse::<i32>().unwrap() };
-          y += run_count;
-          x = x_offset;
+          y = advance(y, run_count(&run_count_str)?)?;
+          x = 0;
           run_count_str = "0".to_owned();
         },
         '!' => {
-          break;
+          break 'lines;
         },
         _ => {}
       }
     }
   };
 
+  Ok(cells)
+}
+
+// Rule from the header line, e.g. "x = 3, y = 3, rule = B36/S23".
+pub fn parse_rule(contents: &str) -> Option<Rule> {
+  Rule::parse(rule_name(contents)?).ok()
+}
+
+fn rule_name(contents: &str) -> Option<&str> {
+  let header = contents.lines().find(|l| l.trim_start().starts_with('x'))?;
+  let (_, rule) = header.split_once("rule")?;
+  let rule = rule.trim_start().strip_prefix('=')?;
+  Some(rule.split(',').next()?.trim())
+}
+
+pub struct Header {
+  pub name: Option<String>,
+  pub author: Option<String>,
+  pub width: i32,
+  pub height: i32,
+}
+
+// Name and author from the "#N" and "#O" comment lines, size from the "x = 3, y = 3" header line.
+pub fn parse_header(contents: &str) -> Header {
+  let comment = |tag: &str| contents.lines()
+    .find_map(|l| l.strip_prefix(tag))
+    .map(|c| c.trim().to_string())
+    .filter(|c| !c.is_empty());
+  let mut header = Header { name: comment("#N"), author: comment("#O"), width: 0, height: 0 };
+
+  if let Some(line) = contents.lines().find(|l| l.trim_start().starts_with('x')) {
+    for field in line.split(',') {
+      match field.split_once('=') {
+        Some((key, value)) if key.trim() == "x" => header.width = value.trim().parse().unwrap_or(0),
+        Some((key, value)) if key.trim() == "y" => header.height = value.trim().parse().unwrap_or(0),
+        _ => {}
+      }
+    }
+  }
+
+  header
+}
+
+fn run_count(run_count_str: &str) -> Result<i32, String> {
+  if run_count_str == "0" { return Ok(1) }
+  let count = run_count_str.parse::<i32>().map_err(|e| format!("invalid run count {}: {}", run_count_str, e))?;
+  if count > MAX_RUN_COUNT { return Err(format!("run count {} is larger than {}", count, MAX_RUN_COUNT)) }
+  Ok(count)
+}
+
+fn advance(position: i32, count: i32) -> Result<i32, String> {
+  position.checked_add(count).ok_or("pattern is too large".to_string())
+}
+
+pub fn save_pattern(name: &str, cells: &[Coord], rule: &Rule) -> Result<(), String> {
+  fs::write(name, encode_pattern(cells, rule)).map_err(|e| format!("could not write {}: {}", name, e))?;
+  println!("saved pattern: {}", name);
+
   Ok(())
 }
+
+// Encodes cells relative to their bounding box, so the pattern always starts at (0, 0).
+pub fn encode_pattern(cells: &[Coord], rule: &Rule) -> String {
+  let states: Vec<(Coord, u8)> = cells.iter().map(|c| (*c, 1)).collect();
+  encode_states(&states, &rule.to_string(), |state| if state == 0 { 'b' } else { 'o' })
+}
+
+// Encodes cells with multiple states using the multi-state tags, '.' for state 0 and 'A' to 'X' for states 1 to 24.
+pub fn encode_multistate(cells: &[(Coord, u8)], rule: &str) -> String {
+  encode_states(cells, rule, |state| if state == 0 { '.' } else { (b'A' + state - 1) as char })
+}
+
+fn encode_states(cells: &[(Coord, u8)], rule: &str, tag: impl Fn(u8) -> char) -> String {
+  let coords: Vec<Coord> = cells.iter().map(|(c, _)| *c).collect();
+  let (min, width, height) = match pattern::bounding_box(&coords) {
+    Some((min, max)) => (min, max.x - min.x + 1, max.y - min.y + 1),
+    None => (Coord::new(0, 0), 0, 0)
+  };
+  let mut rows: Vec<Vec<(i32, u8)>> = vec![Vec::new(); height as usize];
+  for (coord, state) in cells {
+    rows[(coord.y - min.y) as usize].push((coord.x - min.x, *state));
+  }
+
+  let mut writer = RunWriter::new();
+  let mut pending_rows = 0;
+  for row in rows.iter_mut() {
+    if row.is_empty() {
+      pending_rows += 1;
+      continue
+    }
+    writer.push(pending_rows, '$');
+    pending_rows = 1;
+    row.sort_unstable();
+    row.dedup_by_key(|(x, _)| *x);
+    let mut x = 0;
+    let mut i = 0;
+    while i < row.len() {
+      let (start, state) = row[i];
+      while i + 1 < row.len() && row[i + 1].0 == row[i].0 + 1 && row[i + 1].1 == state { i += 1 }
+      writer.push(start - x, tag(0));
+      writer.push(row[i].0 - start + 1, tag(state));
+      x = row[i].0 + 1;
+      i += 1;
+    }
+  }
+  writer.push(1, '!');
+
+  format!("x = {}, y = {}, rule = {}\n{}\n", width, height, rule, writer.finish())
+}
+
+struct RunWriter {
+  lines: Vec<String>,
+  line: String,
+}
+
+impl RunWriter {
+  fn new() -> RunWriter {
+    RunWriter { lines: Vec::new(), line: String::new() }
+  }
+
+  fn push(&mut self, count: i32, tag: char) {
+    if count <= 0 { return }
+    let run = if count == 1 { tag.to_string() } else { format!("{}{}", count, tag) };
+    if self.line.len() + run.len() > MAX_LINE_LENGTH {
+      self.lines.push(std::mem::take(&mut self.line));
+    }
+    self.line.push_str(&run);
+  }
+
+  fn finish(mut self) -> String {
+    self.lines.push(self.line);
+    self.lines.join("\n")
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn sorted(cells: &[Coord]) -> Vec<(i32, i32)> {
+    let mut cells: Vec<(i32, i32)> = cells.iter().map(|c| (c.x, c.y)).collect();
+    cells.sort();
+    cells
+  }
+
+  #[test]
+  fn parses_runs_and_rows() {
+    let glider = "#N Glider\nx = 3, y = 3, rule = B3/S23\nbob$2bo$3o!";
+    assert_eq!(sorted(&parse_pattern(glider).unwrap()), vec![(0, 2), (1, 0), (1, 2), (2, 1), (2, 2)]);
+    assert_eq!(sorted(&parse_pattern("o2$o!").unwrap()), vec![(0, 0), (0, 2)]);
+    assert_eq!(parse_rule(glider).map(|r| r.to_string()), Some("B3/S23".to_string()));
+  }
+
+  #[test]
+  fn round_trips_encoded_patterns() {
+    let cells: Vec<Coord> = [(0, 0), (1, 0), (5, 0), (2, 3), (3, 3), (70, 3), (0, 9)].iter().map(|(x, y)| Coord::new(*x, *y)).collect();
+    let encoded = encode_pattern(&cells, &Rule::life());
+    assert!(encoded.lines().all(|l| l.len() <= MAX_LINE_LENGTH));
+    assert_eq!(sorted(&parse_pattern(&encoded).unwrap()), sorted(&cells));
+  }
+
+  #[test]
+  fn encodes_relative_to_the_bounding_box() {
+    let cells = [Coord::new(10, -5), Coord::new(11, -5), Coord::new(12, -5)];
+    assert_eq!(encode_pattern(&cells, &Rule::life()), "x = 3, y = 1, rule = B3/S23\n3o!\n");
+  }
+
+  #[test]
+  fn rejects_huge_run_counts() {
+    assert!(parse_pattern("2147483647b2b!").is_err());
+    assert!(parse_pattern("2000000000o!").is_err());
+    assert!(parse_pattern("99999999999$o!").is_err());
+    assert!(parse_pattern(&"16777216b".repeat(200)).is_err());
+    assert!(parse_pattern(&"16777216$".repeat(200)).is_err());
+    assert!(parse_pattern("16777216b16777216bo!").is_ok());
+  }
+
+  #[test]
+  fn reads_odd_life_history_states_as_alive() {
+    let states = [(Coord::new(0, 0), 1), (Coord::new(1, 0), 2), (Coord::new(2, 0), 5), (Coord::new(0, 1), 6)];
+    let encoded = encode_multistate(&states, "LifeHistory");
+    assert_eq!(sorted(&parse_pattern(&encoded).unwrap()), vec![(0, 0), (2, 0)]);
+  }
+
+  #[test]
+  fn rejects_multi_state_cells_outside_life_history() {
+    assert!(parse_pattern("x = 2, y = 1, rule = B3/S23\nAB!").is_err());
+    assert!(parse_pattern("x = 2, y = 1, rule = Generations\nA.!").is_err());
+    assert!(parse_pattern("AB!").is_err());
+    assert!(parse_pattern("x = 1, y = 1, rule = LifeHistory\npA!").is_err());
+    assert!(parse_pattern("x = 1, y = 1, rule = LifeHistory\nxC!").is_err());
+  }
+}
@@ -0,0 +1,60 @@
+use std::fmt;
+
+// Outer totalistic rule in B/S notation, https://conwaylife.com/wiki/Rulestring
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Rule {
+    pub birth: [bool; 9],
+    pub survival: [bool; 9],
+}
+
+impl Rule {
+    pub fn life() -> Rule {
+        Rule::parse("B3/S23").expect("valid rule")
+    }
+
+    // Accepts "B36/S23" as well as the older "23/36" survival/birth notation.
+    pub fn parse(rule: &str) -> Result<Rule, String> {
+        let rule = rule.trim();
+        let (birth, survival) = match rule.split_once('/') {
+            Some((b, s)) if b.starts_with(['B', 'b']) && s.starts_with(['S', 's']) => (&b[1..], &s[1..]),
+            Some((s, b)) if s.starts_with(['S', 's']) && b.starts_with(['B', 'b']) => (&b[1..], &s[1..]),
+            Some((s, b)) if s.chars().chain(b.chars()).all(|c| c.is_ascii_digit()) => (b, s),
+            _ => return Err(format!("invalid rule: {}", rule))
+        };
+
+        let birth = neighbor_counts(birth).ok_or(format!("invalid rule: {}", rule))?;
+        let survival = neighbor_counts(survival).ok_or(format!("invalid rule: {}", rule))?;
+        // B0 rules would turn on the infinite empty background, which the sparse universe can't represent.
+        if birth[0] { return Err(format!("B0 rules are not supported: {}", rule)) }
+
+        Ok(Rule { birth, survival })
+    }
+
+    pub fn next_state(&self, alive: bool, neighbor_count: u8) -> bool {
+        if alive { self.survival[neighbor_count as usize] } else { self.birth[neighbor_count as usize] }
+    }
+}
+
+fn neighbor_counts(digits: &str) -> Option<[bool; 9]> {
+    let mut counts = [false; 9];
+    for c in digits.chars() {
+        let count = c.to_digit(10)? as usize;
+        if count > 8 { return None }
+        counts[count] = true;
+    }
+    Some(counts)
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "B")?;
+        for (count, birth) in self.birth.iter().enumerate() {
+            if *birth { write!(f, "{}", count)? }
+        }
+        write!(f, "/S")?;
+        for (count, survival) in self.survival.iter().enumerate() {
+            if *survival { write!(f, "{}", count)? }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,351 @@
+// Small conflict driven SAT solver: unit propagation with two watched literals, clause learning at the first unique
+// implication point, and decisions on the most active variable. Literals are variables numbered from 1, negated for
+// their negation, as in the DIMACS format.
+pub type Lit = i32;
+
+pub enum Outcome {
+    // Value of every variable, indexed by variable number, index 0 unused.
+    Satisfiable(Vec<bool>),
+    Unsatisfiable,
+    // Gave up after the conflict limit.
+    Unknown,
+}
+
+const ACTIVITY_DECAY: f64 = 0.95;
+
+#[derive(Default)]
+pub struct Solver {
+    clauses: Vec<Vec<Lit>>,
+    // Clauses watching each literal, indexed by index().
+    watches: Vec<Vec<usize>>,
+    values: Vec<Option<bool>>,
+    levels: Vec<usize>,
+    reasons: Vec<Option<usize>>,
+    activity: Vec<f64>,
+    increment: f64,
+    trail: Vec<Lit>,
+    trail_limits: Vec<usize>,
+    propagated: usize,
+    unsatisfiable: bool,
+}
+
+fn var(lit: Lit) -> usize {
+    lit.unsigned_abs() as usize
+}
+
+fn index(lit: Lit) -> usize {
+    2 * var(lit) + (lit < 0) as usize
+}
+
+impl Solver {
+    pub fn new() -> Solver {
+        Solver {
+            watches: vec![Vec::new(); 2],
+            values: vec![None],
+            levels: vec![0],
+            reasons: vec![None],
+            activity: vec![0.0],
+            increment: 1.0,
+            ..Solver::default()
+        }
+    }
+
+    pub fn new_var(&mut self) -> Lit {
+        self.values.push(None);
+        self.levels.push(0);
+        self.reasons.push(None);
+        self.activity.push(0.0);
+        self.watches.push(Vec::new());
+        self.watches.push(Vec::new());
+        (self.values.len() - 1) as Lit
+    }
+
+    pub fn var_count(&self) -> usize {
+        self.values.len() - 1
+    }
+
+    fn value(&self, lit: Lit) -> Option<bool> {
+        self.values[var(lit)].map(|v| v == (lit > 0))
+    }
+
+    fn level(&self) -> usize {
+        self.trail_limits.len()
+    }
+
+    // Adds a clause, which may also be done between calls to solve, e.g. to block a solution found before.
+    pub fn add_clause(&mut self, lits: &[Lit]) {
+        self.backtrack(0);
+        let mut clause: Vec<Lit> = Vec::new();
+        for lit in lits {
+            match self.value(*lit) {
+                Some(true) => return,
+                Some(false) => continue,
+                None if clause.contains(&-lit) => return,
+                None if !clause.contains(lit) => clause.push(*lit),
+                None => {}
+            }
+        }
+        match clause.len() {
+            0 => self.unsatisfiable = true,
+            1 => self.enqueue(clause[0], None),
+            _ => { self.attach(clause); }
+        }
+    }
+
+    fn attach(&mut self, clause: Vec<Lit>) -> usize {
+        let i = self.clauses.len();
+        self.watches[index(clause[0])].push(i);
+        self.watches[index(clause[1])].push(i);
+        self.clauses.push(clause);
+        i
+    }
+
+    fn enqueue(&mut self, lit: Lit, reason: Option<usize>) {
+        let v = var(lit);
+        self.values[v] = Some(lit > 0);
+        self.levels[v] = self.level();
+        self.reasons[v] = reason;
+        self.trail.push(lit);
+    }
+
+    // Assigns everything implied by the trail, returning a clause with all literals false if there is a conflict.
+    fn propagate(&mut self) -> Option<usize> {
+        while self.propagated < self.trail.len() {
+            let false_lit = -self.trail[self.propagated];
+            self.propagated += 1;
+            let mut watching = std::mem::take(&mut self.watches[index(false_lit)]);
+            let mut i = 0;
+            while i < watching.len() {
+                let c = watching[i];
+                let clause = &mut self.clauses[c];
+                if clause[0] == false_lit { clause.swap(0, 1) }
+                let first = clause[0];
+                if self.values[var(first)].map(|v| v == (first > 0)) == Some(true) {
+                    i += 1;
+                    continue
+                }
+
+                let replacement = (2..clause.len()).find(|k| {
+                    let lit = clause[*k];
+                    self.values[var(lit)].map(|v| v == (lit > 0)) != Some(false)
+                });
+                match replacement {
+                    Some(k) => {
+                        clause.swap(1, k);
+                        let watch = index(clause[1]);
+                        self.watches[watch].push(c);
+                        watching.swap_remove(i);
+                    },
+                    None if self.value(first) == Some(false) => {
+                        self.watches[index(false_lit)] = watching;
+                        return Some(c)
+                    },
+                    None => {
+                        self.enqueue(first, Some(c));
+                        i += 1;
+                    }
+                }
+            }
+            self.watches[index(false_lit)] = watching;
+        }
+        None
+    }
+
+    // Learns a clause from the conflict that would have implied the opposite of a decision sooner, returning it with
+    // the literal to assert first and the level to go back to.
+    fn analyze(&mut self, conflict: usize) -> (Vec<Lit>, usize) {
+        let mut seen = vec![false; self.values.len()];
+        let mut learnt = vec![0];
+        let mut pending = 0;
+        let mut clause = conflict;
+        // The literal a reason clause implied, which is true and not part of the explanation.
+        let mut implied = None;
+        let mut i = self.trail.len();
+        let asserting = loop {
+            for k in 0..self.clauses[clause].len() {
+                let lit = self.clauses[clause][k];
+                let v = var(lit);
+                if implied == Some(v) || seen[v] || self.levels[v] == 0 { continue }
+                seen[v] = true;
+                self.bump(v);
+                if self.levels[v] == self.level() { pending += 1 } else { learnt.push(lit) }
+            }
+            loop {
+                i -= 1;
+                if seen[var(self.trail[i])] { break }
+            }
+            let lit = self.trail[i];
+            seen[var(lit)] = false;
+            pending -= 1;
+            if pending == 0 { break lit }
+            clause = self.reasons[var(lit)].expect("implied literal has a reason");
+            implied = Some(var(lit));
+        };
+        learnt[0] = -asserting;
+
+        let mut backjump = 0;
+        if learnt.len() > 1 {
+            let deepest = (1..learnt.len()).max_by_key(|k| self.levels[var(learnt[*k])]).unwrap_or(1);
+            learnt.swap(1, deepest);
+            backjump = self.levels[var(learnt[1])];
+        }
+        self.increment /= ACTIVITY_DECAY;
+        (learnt, backjump)
+    }
+
+    fn bump(&mut self, v: usize) {
+        self.activity[v] += self.increment;
+        if self.activity[v] > 1e100 {
+            for a in self.activity.iter_mut() { *a *= 1e-100 }
+            self.increment *= 1e-100;
+        }
+    }
+
+    fn backtrack(&mut self, level: usize) {
+        if self.level() <= level { return }
+        let limit = self.trail_limits[level];
+        for lit in self.trail.drain(limit..) {
+            self.values[var(lit)] = None;
+            self.reasons[var(lit)] = None;
+        }
+        self.trail_limits.truncate(level);
+        self.propagated = self.propagated.min(limit);
+    }
+
+    // Unassigned variable with the highest activity, the lowest numbered one on ties.
+    fn pick(&self) -> Option<usize> {
+        let mut best: Option<usize> = None;
+        for v in 1..self.values.len() {
+            if self.values[v].is_some() { continue }
+            if best.is_none_or(|b| self.activity[v] > self.activity[b]) { best = Some(v) }
+        }
+        best
+    }
+
+    // Searches for an assignment satisfying all clauses, giving up after max_conflicts conflicts. Variables are tried
+    // false first, which suits sparse patterns.
+    pub fn solve(&mut self, max_conflicts: u64) -> Outcome {
+        if self.unsatisfiable { return Outcome::Unsatisfiable }
+        let mut conflicts = 0;
+        let outcome = loop {
+            if let Some(conflict) = self.propagate() {
+                if self.level() == 0 {
+                    self.unsatisfiable = true;
+                    break Outcome::Unsatisfiable
+                }
+                conflicts += 1;
+                if conflicts > max_conflicts { break Outcome::Unknown }
+                let (learnt, backjump) = self.analyze(conflict);
+                self.backtrack(backjump);
+                if learnt.len() == 1 {
+                    self.enqueue(learnt[0], None);
+                } else {
+                    let asserting = learnt[0];
+                    let c = self.attach(learnt);
+                    self.enqueue(asserting, Some(c));
+                }
+                continue
+            }
+
+            match self.pick() {
+                Some(v) => {
+                    self.trail_limits.push(self.trail.len());
+                    self.enqueue(-(v as Lit), None);
+                },
+                None => break Outcome::Satisfiable(self.values.iter().map(|v| v.unwrap_or(false)).collect())
+            }
+        };
+        self.backtrack(0);
+        outcome
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    fn solver(vars: usize, clauses: &[Vec<Lit>]) -> Solver {
+        let mut solver = Solver::new();
+        for _ in 0..vars { solver.new_var(); }
+        for clause in clauses { solver.add_clause(clause); }
+        solver
+    }
+
+    fn satisfies(model: &[bool], clauses: &[Vec<Lit>]) -> bool {
+        clauses.iter().all(|clause| clause.iter().any(|lit| model[var(*lit)] == (*lit > 0)))
+    }
+
+    fn brute_force_count(vars: usize, clauses: &[Vec<Lit>]) -> usize {
+        (0u32..1 << vars).filter(|bits| {
+            let model: Vec<bool> = (0..=vars).map(|v| v > 0 && bits & (1 << (v - 1)) != 0).collect();
+            satisfies(&model, clauses)
+        }).count()
+    }
+
+    // Pigeon p is in hole h when variable p * holes + h + 1 is true.
+    fn pigeonhole(pigeons: usize, holes: usize) -> Vec<Vec<Lit>> {
+        let lit = |p: usize, h: usize| (p * holes + h + 1) as Lit;
+        let mut clauses: Vec<Vec<Lit>> = (0..pigeons).map(|p| (0..holes).map(|h| lit(p, h)).collect()).collect();
+        for h in 0..holes {
+            for p in 0..pigeons {
+                for q in p + 1..pigeons { clauses.push(vec![-lit(p, h), -lit(q, h)]) }
+            }
+        }
+        clauses
+    }
+
+    #[test]
+    fn finds_models_of_satisfiable_formulas() {
+        let clauses = vec![vec![1, 2], vec![-1, 3], vec![-2, -3], vec![2, 3]];
+        match solver(3, &clauses).solve(1000) {
+            Outcome::Satisfiable(model) => assert!(satisfies(&model, &clauses)),
+            _ => panic!("expected a model"),
+        }
+    }
+
+    #[test]
+    fn proves_unsatisfiable_formulas() {
+        let all_pairs = vec![vec![1, 2], vec![1, -2], vec![-1, 2], vec![-1, -2]];
+        assert!(matches!(solver(2, &all_pairs).solve(1000), Outcome::Unsatisfiable));
+        assert!(matches!(solver(0, &[vec![]]).solve(1000), Outcome::Unsatisfiable));
+        assert!(matches!(solver(20, &pigeonhole(5, 4)).solve(100_000), Outcome::Unsatisfiable));
+    }
+
+    #[test]
+    fn gives_up_after_max_conflicts() {
+        assert!(matches!(solver(56, &pigeonhole(8, 7)).solve(10), Outcome::Unknown));
+    }
+
+    // Random 3-SAT around the satisfiability threshold, each formula solved and then its models counted by blocking
+    // every model found, compared with trying every assignment.
+    #[test]
+    fn agrees_with_brute_force() {
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..300 {
+            let vars = rng.gen_range(1..=10);
+            let clauses: Vec<Vec<Lit>> = (0..rng.gen_range(1..=45)).map(|_| {
+                (0..rng.gen_range(1..=3)).map(|_| {
+                    let v = rng.gen_range(1..=vars) as Lit;
+                    if rng.gen_bool(0.5) { v } else { -v }
+                }).collect()
+            }).collect();
+
+            let mut solver = solver(vars, &clauses);
+            let mut count = 0;
+            loop {
+                match solver.solve(100_000) {
+                    Outcome::Satisfiable(model) => {
+                        assert!(satisfies(&model, &clauses));
+                        count += 1;
+                        let blocking: Vec<Lit> = (1..=vars).map(|v| if model[v] { -(v as Lit) } else { v as Lit }).collect();
+                        solver.add_clause(&blocking);
+                    },
+                    Outcome::Unsatisfiable => break,
+                    Outcome::Unknown => panic!("gave up on a small formula"),
+                }
+            }
+            assert_eq!(count, brute_force_count(vars, &clauses));
+        }
+    }
+}
@@ -0,0 +1,356 @@
+use std::fs;
+
+use crate::apgcode;
+use crate::coord::Coord;
+use crate::rle;
+use crate::rule::Rule;
+use crate::sat::{Lit, Outcome, Solver};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Symmetry {
+    None,
+    // Unchanged by a half turn.
+    C2,
+    // Unchanged by a quarter turn, needs a square box.
+    C4,
+    // Unchanged by a left to right reflection.
+    D2,
+    // Unchanged by both the left to right and the top to bottom reflection.
+    D4,
+    // Unchanged by all rotations and reflections, needs a square box.
+    D8,
+}
+
+impl Symmetry {
+    pub fn parse(name: &str) -> Result<Symmetry, String> {
+        match name.to_uppercase().as_str() {
+            "NONE" | "C1" => Ok(Symmetry::None),
+            "C2" => Ok(Symmetry::C2),
+            "C4" => Ok(Symmetry::C4),
+            "D2" => Ok(Symmetry::D2),
+            "D4" => Ok(Symmetry::D4),
+            "D8" => Ok(Symmetry::D8),
+            _ => Err(format!("unknown symmetry: {}, expected one of C1, C2, C4, D2, D4, D8", name))
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Symmetry::None => "C1",
+            Symmetry::C2 => "C2",
+            Symmetry::C4 => "C4",
+            Symmetry::D2 => "D2",
+            Symmetry::D4 => "D4",
+            Symmetry::D8 => "D8",
+        }
+    }
+
+    // Transformations the symmetric patterns are unchanged by, enough to generate all the others.
+    fn generators(&self) -> Vec<Transform> {
+        let transform = |swap, flip_x, flip_y| Transform { swap, flip_x, flip_y };
+        match self {
+            Symmetry::None => vec![],
+            Symmetry::C2 => vec![transform(false, true, true)],
+            Symmetry::C4 => vec![transform(true, true, false)],
+            Symmetry::D2 => vec![transform(false, true, false)],
+            Symmetry::D4 => vec![transform(false, true, false), transform(false, false, true)],
+            Symmetry::D8 => vec![transform(false, true, false), transform(true, false, false)],
+        }
+    }
+}
+
+// Rotation or reflection of the box onto itself: swapping the axes, then mirroring them.
+#[derive(Clone, Copy)]
+struct Transform {
+    swap: bool,
+    flip_x: bool,
+    flip_y: bool,
+}
+
+impl Transform {
+    fn apply(&self, coord: Coord, width: i32, height: i32) -> Coord {
+        let (x, y) = if self.swap { (coord.y, coord.x) } else { (coord.x, coord.y) };
+        Coord::new(if self.flip_x { width - 1 - x } else { x }, if self.flip_y { height - 1 - y } else { y })
+    }
+}
+
+#[derive(Clone)]
+pub struct SearchOptions {
+    pub width: i32,
+    pub height: i32,
+    // 1 for still lifes.
+    pub period: u64,
+    pub symmetry: Symmetry,
+    pub rule: Rule,
+    // Conflicts the solver may run into looking for each pattern before giving up.
+    pub max_conflicts: u64,
+}
+
+pub enum SearchResult {
+    Found(Vec<Coord>),
+    // Every pattern has been found.
+    Exhausted,
+    // The solver gave up after max_conflicts conflicts.
+    Unknown,
+}
+
+// Looks for still lifes or oscillators that exactly fill a box, one phase per generation of the period. Every cell
+// of the box is a variable in each phase, shared by the cells the symmetry maps onto each other, and each phase has
+// to evolve into the next one, the last into the first. Each pattern found is ruled out, in all its phases and
+// orientations, before looking for the next one.
+pub struct Search {
+    options: SearchOptions,
+    solver: Solver,
+    // Variables of the cells of the box, row by row, for each phase.
+    phases: Vec<Vec<Lit>>,
+}
+
+impl Search {
+    pub fn new(options: SearchOptions) -> Result<Search, String> {
+        let (width, height) = (options.width, options.height);
+        if width < 1 || height < 1 { return Err("the box must be at least 1 by 1".to_string()) }
+        if options.period < 1 { return Err("the period must be at least 1".to_string()) }
+        if width != height && matches!(options.symmetry, Symmetry::C4 | Symmetry::D8) {
+            return Err(format!("{} symmetry needs a square box", options.symmetry.name()))
+        }
+        if options.rule.next_state(false, 0) { return Err("rules with B0 are not supported".to_string()) }
+
+        let mut solver = Solver::new();
+        let generators = options.symmetry.generators();
+        let phases: Vec<Vec<Lit>> = (0..options.period).map(|_| {
+            let mut vars: Vec<Option<Lit>> = vec![None; (width * height) as usize];
+            for i in 0..vars.len() {
+                if vars[i].is_some() { continue }
+                // Every cell the symmetry maps this one onto shares its variable.
+                let var = solver.new_var();
+                let mut orbit = vec![Coord::new(i as i32 % width, i as i32 / width)];
+                while let Some(coord) = orbit.pop() {
+                    let index = (coord.y * width + coord.x) as usize;
+                    if vars[index].is_some() { continue }
+                    vars[index] = Some(var);
+                    orbit.extend(generators.iter().map(|t| t.apply(coord, width, height)));
+                }
+            }
+            vars.into_iter().flatten().collect()
+        }).collect();
+
+        let mut search = Search { options, solver, phases };
+        search.constrain_evolution();
+        search.constrain_edges();
+        search.constrain_period();
+        Ok(search)
+    }
+
+    fn var(&self, phase: usize, coord: Coord) -> Option<Lit> {
+        let (width, height) = (self.options.width, self.options.height);
+        if coord.x < 0 || coord.y < 0 || coord.x >= width || coord.y >= height { return None }
+        Some(self.phases[phase][(coord.y * width + coord.x) as usize])
+    }
+
+    // Each phase evolves into the next. Cells next to the box have to stay dead, the ones further away have no live
+    // neighbors.
+    fn constrain_evolution(&mut self) {
+        let period = self.phases.len();
+        for phase in 0..period {
+            for y in -1..=self.options.height {
+                for x in -1..=self.options.width {
+                    let coord = Coord::new(x, y);
+                    let center = self.var(phase, coord);
+                    let neighbors: Vec<Lit> = coord.neighbors().iter().filter_map(|c| self.var(phase, *c)).collect();
+                    let next = self.var((phase + 1) % period, coord);
+                    transition(&mut self.solver, center, &neighbors, next, &self.options.rule);
+                }
+            }
+        }
+    }
+
+    // Some phase has a live cell on every edge of the box, so that a pattern is only found once rather than at every
+    // position it fits at, and is no smaller than the box.
+    fn constrain_edges(&mut self) {
+        let (width, height) = (self.options.width, self.options.height);
+        let edges: [Vec<Coord>; 4] = [
+            (0..width).map(|x| Coord::new(x, 0)).collect(),
+            (0..width).map(|x| Coord::new(x, height - 1)).collect(),
+            (0..height).map(|y| Coord::new(0, y)).collect(),
+            (0..height).map(|y| Coord::new(width - 1, y)).collect(),
+        ];
+        for edge in edges {
+            let clause: Vec<Lit> = (0..self.phases.len()).flat_map(|p| edge.iter().map(move |c| (p, *c)))
+                .filter_map(|(p, c)| self.var(p, c)).collect();
+            self.solver.add_clause(&clause);
+        }
+    }
+
+    // The first phase comes back no sooner than the period, i.e. differs from the phase after each largest proper
+    // divisor of the period. Every cell gets a variable that can only be true if the cell differs between the two.
+    fn constrain_period(&mut self) {
+        let period = self.phases.len();
+        for divisor in (1..period).filter(|d| period.is_multiple_of(*d) && is_prime(period / d)) {
+            let mut differs = Vec::new();
+            for (first, later) in self.phases[0].clone().into_iter().zip(self.phases[divisor].clone()) {
+                let var = self.solver.new_var();
+                self.solver.add_clause(&[-var, first, later]);
+                self.solver.add_clause(&[-var, -first, -later]);
+                differs.push(var);
+            }
+            self.solver.add_clause(&differs);
+        }
+    }
+
+    // Looks for the next pattern, in its first phase with the top left corner of the box at (0, 0).
+    pub fn find_next(&mut self) -> SearchResult {
+        let model = match self.solver.solve(self.options.max_conflicts) {
+            Outcome::Satisfiable(model) => model,
+            Outcome::Unsatisfiable => return SearchResult::Exhausted,
+            Outcome::Unknown => return SearchResult::Unknown,
+        };
+        let (width, height) = (self.options.width, self.options.height);
+        let phases: Vec<Vec<Coord>> = self.phases.iter().map(|vars| {
+            vars.iter().enumerate().filter(|(_, var)| model[**var as usize])
+                .map(|(i, _)| Coord::new(i as i32 % width, i as i32 / width)).collect()
+        }).collect();
+
+        let mut transforms = Vec::new();
+        for swap in [false, true] {
+            if swap && width != height { continue }
+            for flip_x in [false, true] {
+                for flip_y in [false, true] { transforms.push(Transform { swap, flip_x, flip_y }) }
+            }
+        }
+        for cells in &phases {
+            for transform in &transforms {
+                let image: Vec<Coord> = cells.iter().map(|c| transform.apply(*c, width, height)).collect();
+                self.block(&image);
+            }
+        }
+        SearchResult::Found(phases[0].clone())
+    }
+
+    // Rules out the first phase being exactly these cells.
+    fn block(&mut self, cells: &[Coord]) {
+        let width = self.options.width;
+        let clause: Vec<Lit> = self.phases[0].iter().enumerate().map(|(i, var)| {
+            if cells.contains(&Coord::new(i as i32 % width, i as i32 / width)) { -var } else { *var }
+        }).collect();
+        self.solver.add_clause(&clause);
+    }
+}
+
+// Requires the cell to be in the state of next in the following generation, or dead without next. Cells outside
+// the box are dead, so only the assignments of the center and neighbors inside it are enumerated.
+fn transition(solver: &mut Solver, center: Option<Lit>, neighbors: &[Lit], next: Option<Lit>, rule: &Rule) {
+    let lits: Vec<Lit> = center.into_iter().chain(neighbors.iter().copied()).collect();
+    let offset = center.is_some() as usize;
+    for assignment in 0u32..1 << lits.len() {
+        let on = |i: usize| assignment & (1 << i) != 0;
+        let alive = center.is_some() && on(0);
+        let count = (0..neighbors.len()).filter(|i| on(i + offset)).count() as u8;
+        let live = rule.next_state(alive, count);
+        let mut clause: Vec<Lit> = lits.iter().enumerate().map(|(i, lit)| if on(i) { -lit } else { *lit }).collect();
+        match next {
+            Some(next) => clause.push(if live { next } else { -next }),
+            None if live => {},
+            None => continue,
+        }
+        solver.add_clause(&clause);
+    }
+}
+
+fn is_prime(n: usize) -> bool {
+    n >= 2 && (2..n).take_while(|d| d * d <= n).all(|d| !n.is_multiple_of(d))
+}
+
+// Runs the search, writing every pattern to an RLE file in dir named after its apgcode as soon as it is found.
+// Progress is reported line by line as it is made.
+pub fn run(options: &SearchOptions, max_results: usize, dir: &str, mut progress: impl FnMut(String)) -> Result<String, String> {
+    let mut search = Search::new(options.clone())?;
+    fs::create_dir_all(dir).map_err(|e| format!("could not create {}: {}", dir, e))?;
+    let kind = if options.period == 1 { "still lifes".to_string() } else { format!("p{} oscillators", options.period) };
+    progress(format!("searching for {} in a {}x{} box with {} symmetry, rule {}",
+        kind, options.width, options.height, options.symmetry.name(), options.rule));
+
+    let mut found = 0;
+    while found < max_results {
+        let cells = match search.find_next() {
+            SearchResult::Found(cells) => cells,
+            SearchResult::Exhausted => return Ok(format!("found {} {}, search complete", found, kind)),
+            SearchResult::Unknown => return Ok(format!("found {} {}, gave up after {} conflicts", found, kind, options.max_conflicts)),
+        };
+        found += 1;
+        let name = apgcode::encode(&cells, &options.rule, options.period).unwrap_or_else(|_| format!("result_{}", found));
+        let contents = format!("#C {}x{}, {} symmetry\n{}", options.width, options.height, options.symmetry.name(), rle::encode_pattern(&cells, &options.rule));
+        let path = format!("{}/{}.rle", dir, name);
+        fs::write(&path, contents).map_err(|e| format!("could not write {}: {}", path, e))?;
+        progress(format!("{}: {}", found, path));
+    }
+    Ok(format!("found {} {}, stopped at the limit", found, kind))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pattern;
+    use crate::universe::Universe;
+
+    fn options(width: i32, height: i32, period: u64, symmetry: Symmetry) -> SearchOptions {
+        SearchOptions { width, height, period, symmetry, rule: Rule::life(), max_conflicts: 1_000_000 }
+    }
+
+    // Every pattern the search finds, until it is exhausted.
+    fn find_all(options: SearchOptions) -> Vec<Vec<Coord>> {
+        let mut search = Search::new(options).unwrap();
+        let mut found = Vec::new();
+        loop {
+            match search.find_next() {
+                SearchResult::Found(cells) => found.push(cells),
+                SearchResult::Exhausted => return found,
+                SearchResult::Unknown => panic!("the search gave up"),
+            }
+        }
+    }
+
+    fn sorted(cells: &[Coord]) -> Vec<(i32, i32)> {
+        let mut cells: Vec<(i32, i32)> = cells.iter().map(|c| (c.x, c.y)).collect();
+        cells.sort();
+        cells
+    }
+
+    #[test]
+    fn finds_only_the_block_in_a_2_by_2_box() {
+        let found = find_all(options(2, 2, 1, Symmetry::None));
+        assert_eq!(found.len(), 1);
+        assert_eq!(sorted(&found[0]), vec![(0, 0), (0, 1), (1, 0), (1, 1)]);
+    }
+
+    #[test]
+    fn finds_only_the_blinker_in_a_3_by_3_box() {
+        let found = find_all(options(3, 3, 2, Symmetry::None));
+        assert_eq!(found.len(), 1);
+        let cells = sorted(&found[0]);
+        assert!(cells == vec![(0, 1), (1, 1), (2, 1)] || cells == vec![(1, 0), (1, 1), (1, 2)]);
+    }
+
+    #[test]
+    fn finds_symmetric_still_lifes_that_fill_the_box() {
+        let rule = Rule::life();
+        let found = find_all(options(4, 4, 1, Symmetry::D8));
+        assert!(!found.is_empty());
+        for cells in &found {
+            let (min, max) = pattern::bounding_box(cells).unwrap();
+            assert_eq!((min.x, min.y, max.x, max.y), (0, 0, 3, 3));
+            let universe: Universe = cells.iter().copied().collect();
+            assert_eq!(sorted(&universe.step(&rule).iter().collect::<Vec<_>>()), sorted(cells));
+            assert_eq!(sorted(&pattern::normalize(&pattern::rotate_cw(cells))), sorted(cells));
+            assert_eq!(sorted(&pattern::normalize(&pattern::flip_horizontal(cells))), sorted(cells));
+        }
+    }
+
+    #[test]
+    fn rejects_impossible_options() {
+        assert!(Search::new(options(3, 3, 0, Symmetry::None)).is_err());
+        assert!(Search::new(options(0, 3, 1, Symmetry::None)).is_err());
+        assert!(Search::new(options(3, 4, 1, Symmetry::C4)).is_err());
+        assert!(Search::new(options(3, 4, 1, Symmetry::D8)).is_err());
+        assert!(Search::new(options(4, 4, 1, Symmetry::C4)).is_ok());
+    }
+}
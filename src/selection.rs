@@ -0,0 +1,202 @@
+use crate::coord::Coord;
+use crate::pattern;
+use crate::rle;
+use crate::state::State;
+
+#[derive(Clone, Copy)]
+pub struct Selection {
+    pub start: Coord,
+    pub end: Coord,
+}
+
+impl Selection {
+    pub fn new(start: Coord, end: Coord) -> Selection {
+        Selection { start, end }
+    }
+
+    pub fn min(&self) -> Coord {
+        Coord::new(self.start.x.min(self.end.x), self.start.y.min(self.end.y))
+    }
+
+    pub fn max(&self) -> Coord {
+        Coord::new(self.start.x.max(self.end.x), self.start.y.max(self.end.y))
+    }
+
+    pub fn width(&self) -> i32 {
+        self.max().x - self.min().x + 1
+    }
+
+    pub fn height(&self) -> i32 {
+        self.max().y - self.min().y + 1
+    }
+
+    pub fn contains(&self, coord: &Coord) -> bool {
+        let min = self.min();
+        let max = self.max();
+        coord.x >= min.x && coord.x <= max.x && coord.y >= min.y && coord.y <= max.y
+    }
+}
+
+pub fn selected_cells(state: &State) -> Vec<Coord> {
+    match state.selection {
+        Some(selection) => state.cell_coords.cells_in_rect(selection.min(), selection.max()),
+        None => Vec::new()
+    }
+}
+
+pub fn copy(state: &mut State) {
+    if state.selection.is_none() { return }
+    state.clipboard = Some(rle::encode_pattern(&selected_cells(state), &state.rule));
+}
+
+pub fn cut(state: &mut State) {
+    copy(state);
+    clear_inside(state);
+}
+
+// Pastes the clipboard with its top left corner at the given cell and selects the pasted area.
+pub fn paste(state: &mut State, at: Coord) -> Result<(), String> {
+    let clipboard = match &state.clipboard { Some(c) => c, None => return Ok(()) };
+    let cells = rle::parse_pattern(clipboard)?;
+    let (width, height) = match pattern::bounding_box(&cells) {
+        Some((_, max)) => (max.x + 1, max.y + 1),
+        None => (1, 1)
+    };
+    for coord in cells {
+        state.insert_cell(Coord::new(coord.x + at.x, coord.y + at.y));
+    }
+    state.selection = Some(Selection::new(at, Coord::new(at.x + width - 1, at.y + height - 1)));
+
+    Ok(())
+}
+
+pub fn save(state: &State, path: &str) -> Result<(), String> {
+    if state.selection.is_none() { return Err("nothing selected".to_string()) }
+    rle::save_pattern(path, &selected_cells(state), &state.rule)
+}
+
+pub fn load_clipboard(state: &mut State, path: &str) -> Result<(), String> {
+    let cells = rle::read_pattern(path)?;
+    state.clipboard = Some(rle::encode_pattern(&cells, &state.rule));
+
+    Ok(())
+}
+
+pub fn clear_inside(state: &mut State) {
+    for coord in selected_cells(state) {
+        state.remove_cell(&coord);
+    }
+}
+
+pub fn clear_outside(state: &mut State) {
+    let selection = match state.selection { Some(s) => s, None => return };
+    let outside: Vec<Coord> = state.cell_coords.iter().filter(|c| !selection.contains(c)).collect();
+    for coord in outside {
+        state.remove_cell(&coord);
+    }
+}
+
+pub fn random_fill(state: &mut State, density: f32) {
+    let selection = match state.selection { Some(s) => s, None => return };
+    random_fill_rect(state, selection.min(), selection.max(), density);
+}
+
+// Sets each cell of the rectangle between the two corners (inclusive) live with the given probability.
+pub fn random_fill_rect(state: &mut State, min: Coord, max: Coord, density: f32) {
+    for y in min.y..=max.y {
+        for x in min.x..=max.x {
+            let coord = Coord::new(x, y);
+            if rand::random::<f32>() < density {
+                state.insert_cell(coord);
+            } else {
+                state.remove_cell(&coord);
+            }
+        }
+    }
+}
+
+// Rotates the selection 90 degrees clockwise around its top left corner, swapping its width and height.
+pub fn rotate(state: &mut State) {
+    let selection = match state.selection { Some(s) => s, None => return };
+    let min = selection.min();
+    let height = selection.height();
+    transform(state, |c| Coord::new(min.x + height - 1 - (c.y - min.y), min.y + c.x - min.x));
+    state.selection = Some(Selection::new(min, Coord::new(min.x + height - 1, min.y + selection.width() - 1)));
+}
+
+pub fn flip_horizontal(state: &mut State) {
+    let selection = match state.selection { Some(s) => s, None => return };
+    let (min, max) = (selection.min(), selection.max());
+    transform(state, |c| Coord::new(min.x + max.x - c.x, c.y));
+}
+
+pub fn flip_vertical(state: &mut State) {
+    let selection = match state.selection { Some(s) => s, None => return };
+    let (min, max) = (selection.min(), selection.max());
+    transform(state, |c| Coord::new(c.x, min.y + max.y - c.y));
+}
+
+fn transform(state: &mut State, f: impl Fn(&Coord) -> Coord) {
+    let cells = selected_cells(state);
+    clear_inside(state);
+    for coord in cells.iter().map(f) {
+        state.insert_cell(coord);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state_with(coords: &[(i32, i32)], min: (i32, i32), max: (i32, i32)) -> State {
+        let mut state = State::new();
+        for (x, y) in coords { state.cell_coords.insert(Coord::new(*x, *y)); }
+        state.selection = Some(Selection::new(Coord::new(min.0, min.1), Coord::new(max.0, max.1)));
+        state
+    }
+
+    fn live(state: &State) -> Vec<(i32, i32)> {
+        let mut cells: Vec<(i32, i32)> = state.cell_coords.iter().map(|c| (c.x, c.y)).collect();
+        cells.sort();
+        cells
+    }
+
+    #[test]
+    fn rotates_the_selection_and_swaps_its_size() {
+        // A horizontal domino in the top row of a 3x2 selection, plus a cell outside it.
+        let mut state = state_with(&[(0, 0), (1, 0), (9, 9)], (0, 0), (2, 1));
+        rotate(&mut state);
+        assert_eq!(live(&state), vec![(1, 0), (1, 1), (9, 9)]);
+        let selection = state.selection.unwrap();
+        assert_eq!((selection.width(), selection.height()), (2, 3));
+    }
+
+    #[test]
+    fn flips_inside_the_selection() {
+        let mut state = state_with(&[(0, 0), (0, 1)], (0, 0), (3, 2));
+        flip_horizontal(&mut state);
+        assert_eq!(live(&state), vec![(3, 0), (3, 1)]);
+        flip_vertical(&mut state);
+        assert_eq!(live(&state), vec![(3, 1), (3, 2)]);
+    }
+
+    #[test]
+    fn clears_inside_and_outside() {
+        let mut state = state_with(&[(0, 0), (5, 5)], (0, 0), (1, 1));
+        clear_outside(&mut state);
+        assert_eq!(live(&state), vec![(0, 0)]);
+        clear_inside(&mut state);
+        assert!(live(&state).is_empty());
+    }
+
+    #[test]
+    fn pastes_what_was_cut() {
+        let mut state = state_with(&[(1, 1), (2, 1), (2, 3)], (1, 1), (2, 3));
+        cut(&mut state);
+        assert!(live(&state).is_empty());
+        paste(&mut state, Coord::new(10, 10)).unwrap();
+        assert_eq!(live(&state), vec![(10, 10), (11, 10), (11, 12)]);
+        let selection = state.selection.unwrap();
+        assert_eq!((selection.min().x, selection.min().y, selection.max().x, selection.max().y), (10, 10, 11, 12));
+    }
+}
@@ -0,0 +1,83 @@
+use std::fs;
+
+use crate::bookmarks::Bookmark;
+use crate::camera::View;
+
+// Session file, one bookmark per line with tab separated fields so that names and paths may contain spaces:
+// bookmark <pattern> <name> <x> <y> <cell width> [slot]
+pub fn load(path: &str) -> Vec<Bookmark> {
+    let contents = match fs::read_to_string(path) { Ok(c) => c, Err(_) => return Vec::new() };
+    let mut bookmarks = Vec::new();
+    for (i, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() || line.starts_with('#') { continue }
+        match parse_bookmark(line) {
+            Some(bookmark) => bookmarks.push(bookmark),
+            None => println!("{}: line {}: invalid bookmark", path, i + 1)
+        }
+    }
+    bookmarks
+}
+
+fn parse_bookmark(line: &str) -> Option<Bookmark> {
+    let fields: Vec<&str> = line.split('\t').collect();
+    match fields.as_slice() {
+        ["bookmark", pattern, name, x, y, cell_width, slot @ ..] if slot.len() <= 1 => Some(Bookmark {
+            pattern: pattern.to_string(),
+            name: name.to_string(),
+            view: View { x: x.parse().ok()?, y: y.parse().ok()?, cell_width: cell_width.parse().ok()? },
+            slot: match slot.first() { Some(slot) => Some(slot.parse().ok()?), None => None },
+        }),
+        _ => None
+    }
+}
+
+pub fn save(path: &str, bookmarks: &[Bookmark]) -> Result<(), String> {
+    let mut contents = String::from("# game-of-rust session\n");
+    for b in bookmarks {
+        contents.push_str(&format!("bookmark\t{}\t{}\t{}\t{}\t{}", b.pattern, b.name, b.view.x, b.view.y, b.view.cell_width));
+        if let Some(slot) = b.slot { contents.push_str(&format!("\t{}", slot)) }
+        contents.push('\n');
+    }
+    fs::write(path, contents).map_err(|e| format!("could not write {}: {}", path, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(bookmark: &Bookmark) -> (&str, &str, f32, f32, f32, Option<usize>) {
+        (&bookmark.pattern, &bookmark.name, bookmark.view.x, bookmark.view.y, bookmark.view.cell_width, bookmark.slot)
+    }
+
+    #[test]
+    fn parses_bookmarks_with_and_without_a_slot() {
+        let bookmark = parse_bookmark("bookmark\tpatterns/gosper gun.rle\tthe gun\t-12.5\t40\t8").unwrap();
+        assert_eq!(fields(&bookmark), ("patterns/gosper gun.rle", "the gun", -12.5, 40.0, 8.0, None));
+        let bookmark = parse_bookmark("bookmark\t\tstart\t0\t0\t1.5\t3").unwrap();
+        assert_eq!(fields(&bookmark), ("", "start", 0.0, 0.0, 1.5, Some(3)));
+    }
+
+    #[test]
+    fn rejects_invalid_lines() {
+        assert!(parse_bookmark("bookmark\tp.rle\tname\t0\t0").is_none());
+        assert!(parse_bookmark("bookmark\tp.rle\tname\t0\t0\t1\t2\t3").is_none());
+        assert!(parse_bookmark("bookmark\tp.rle\tname\tleft\t0\t1").is_none());
+        assert!(parse_bookmark("bookmark\tp.rle\tname\t0\t0\t1\tfirst").is_none());
+        assert!(parse_bookmark("bookmark p.rle name 0 0 1").is_none());
+        assert!(parse_bookmark("view\tp.rle\tname\t0\t0\t1").is_none());
+    }
+
+    #[test]
+    fn loads_saved_bookmarks() {
+        let path = std::env::temp_dir().join("game-of-rust-session-test.txt");
+        let path = path.to_str().unwrap();
+        let bookmarks = vec![
+            Bookmark { pattern: "a.rle".to_string(), name: "one".to_string(), view: View { x: 1.0, y: -2.0, cell_width: 4.0 }, slot: Some(1) },
+            Bookmark { pattern: "b.rle".to_string(), name: "two words".to_string(), view: View { x: 0.5, y: 0.0, cell_width: 0.25 }, slot: None },
+        ];
+        save(path, &bookmarks).unwrap();
+        let loaded = load(path);
+        let _ = fs::remove_file(path);
+        assert_eq!(loaded.iter().map(fields).collect::<Vec<_>>(), bookmarks.iter().map(fields).collect::<Vec<_>>());
+    }
+}
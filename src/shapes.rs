@@ -0,0 +1,124 @@
+use crate::coord::Coord;
+
+// http://members.chello.at/easyfilter/bresenham.html
+pub fn line(start: Coord, end: Coord) -> Vec<Coord> {
+    let mut coords = Vec::new();
+    let (mut x, mut y) = (start.x, start.y);
+    let dx = (end.x - start.x).abs();
+    let dy = -(end.y - start.y).abs();
+    let sx = if start.x < end.x { 1 } else { -1 };
+    let sy = if start.y < end.y { 1 } else { -1 };
+    let mut err = dx + dy;
+
+    loop {
+        coords.push(Coord::new(x, y));
+        if x == end.x && y == end.y { break }
+        let e2 = 2 * err;
+        if e2 >= dy { err += dy; x += sx; }
+        if e2 <= dx { err += dx; y += sy; }
+    }
+
+    coords
+}
+
+pub fn rectangle(start: Coord, end: Coord) -> Vec<Coord> {
+    let (x0, x1) = (start.x.min(end.x), start.x.max(end.x));
+    let (y0, y1) = (start.y.min(end.y), start.y.max(end.y));
+    let mut coords = Vec::new();
+    for x in x0..=x1 {
+        coords.push(Coord::new(x, y0));
+        if y1 != y0 { coords.push(Coord::new(x, y1)) }
+    }
+    for y in y0 + 1..y1 {
+        coords.push(Coord::new(x0, y));
+        if x1 != x0 { coords.push(Coord::new(x1, y)) }
+    }
+    coords
+}
+
+// Ellipse inscribed in the rectangle spanned by the two corners.
+pub fn ellipse(start: Coord, end: Coord) -> Vec<Coord> {
+    let mut coords = Vec::new();
+    let (mut x0, mut x1) = (start.x.min(end.x) as i64, start.x.max(end.x) as i64);
+    let (mut y0, mut y1) = (start.y.min(end.y) as i64, start.y.max(end.y) as i64);
+    let mut a = x1 - x0;
+    let b = y1 - y0;
+    let mut b1 = b & 1;
+    let mut dx = 4 * (1 - a) * b * b;
+    let mut dy = 4 * (b1 + 1) * a * a;
+    let mut err = dx + dy + b1 * a * a;
+
+    y0 += (b + 1) / 2;
+    y1 = y0 - b1;
+    a = 8 * a * a;
+    b1 = 8 * b * b;
+
+    let mut plot = |x: i64, y: i64| coords.push(Coord::new(x as i32, y as i32));
+    loop {
+        plot(x1, y0);
+        plot(x0, y0);
+        plot(x0, y1);
+        plot(x1, y1);
+        let e2 = 2 * err;
+        if e2 <= dy { y0 += 1; y1 -= 1; dy += a; err += dy; }
+        if e2 >= dx || 2 * err > dy { x0 += 1; x1 -= 1; dx += b1; err += dx; }
+        if x0 > x1 { break }
+    }
+    // Finishes the tips of narrow ellipses, where the loop above stops too early.
+    while y0 - y1 <= b {
+        plot(x0 - 1, y0);
+        plot(x1 + 1, y0);
+        plot(x0 - 1, y1);
+        plot(x1 + 1, y1);
+        y0 += 1;
+        y1 -= 1;
+    }
+
+    coords
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(coords: &[Coord]) -> Vec<(i32, i32)> {
+        let mut coords: Vec<(i32, i32)> = coords.iter().map(|c| (c.x, c.y)).collect();
+        coords.sort();
+        coords.dedup();
+        coords
+    }
+
+    #[test]
+    fn draws_lines_from_end_to_end() {
+        for (x, y) in [(7, 3), (-5, 2), (3, -8), (-6, -6), (0, 4), (4, 0)] {
+            let line = line(Coord::new(1, 1), Coord::new(1 + x, 1 + y));
+            assert_eq!((line[0].x, line[0].y), (1, 1));
+            assert_eq!((line[line.len() - 1].x, line[line.len() - 1].y), (1 + x, 1 + y));
+            assert_eq!(line.len() as i32, x.abs().max(y.abs()) + 1);
+            assert!(line.windows(2).all(|w| (w[1].x - w[0].x).abs() <= 1 && (w[1].y - w[0].y).abs() <= 1));
+        }
+        assert_eq!(sorted(&line(Coord::new(0, 0), Coord::new(4, 2))), vec![(0, 0), (1, 1), (2, 1), (3, 2), (4, 2)]);
+        assert_eq!(line(Coord::new(2, 2), Coord::new(2, 2)).len(), 1);
+    }
+
+    #[test]
+    fn draws_rectangle_outlines() {
+        assert_eq!(sorted(&rectangle(Coord::new(3, 2), Coord::new(0, 0))).len(), 10);
+        assert_eq!(rectangle(Coord::new(0, 0), Coord::new(4, 0)).len(), 5);
+        assert_eq!(rectangle(Coord::new(0, 0), Coord::new(0, 0)).len(), 1);
+        assert!(!sorted(&rectangle(Coord::new(0, 0), Coord::new(2, 2))).contains(&(1, 1)));
+    }
+
+    #[test]
+    fn inscribes_ellipses_in_the_rectangle() {
+        for (w, h) in [(0, 0), (0, 4), (4, 0), (1, 1), (5, 3), (8, 8), (2, 9), (1, 12), (13, 6)] {
+            let ellipse = sorted(&ellipse(Coord::new(w, h), Coord::new(0, 0)));
+            assert!(ellipse.iter().all(|(x, y)| (0..=w).contains(x) && (0..=h).contains(y)));
+            assert!(ellipse.iter().any(|(x, _)| *x == 0) && ellipse.iter().any(|(x, _)| *x == w));
+            assert!(ellipse.iter().any(|(_, y)| *y == 0) && ellipse.iter().any(|(_, y)| *y == h));
+            // Symmetric in both axes.
+            assert!(ellipse.iter().all(|(x, y)| ellipse.contains(&(w - x, *y)) && ellipse.contains(&(*x, h - y))));
+        }
+        assert_eq!(sorted(&ellipse(Coord::new(0, 0), Coord::new(2, 2))), vec![(0, 1), (1, 0), (1, 2), (2, 1)]);
+    }
+}
@@ -0,0 +1,165 @@
+use std::collections::HashMap;
+use std::fs;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::census::{self, CensusOptions};
+use crate::coord::Coord;
+use crate::rle;
+use crate::rule::Rule;
+use crate::stabilization::{Stabilization, StabilizationDetector};
+use crate::universe::Universe;
+
+// Seeds kept for each object, so that rare finds can be reproduced.
+const SAMPLES_PER_OBJECT: usize = 3;
+
+#[derive(Clone)]
+pub struct SoupOptions {
+    pub size: i32,
+    pub density: f64,
+    pub max_generations: u64,
+    pub rule: Rule,
+    pub census: CensusOptions,
+}
+
+// Square of random cells, always the same for the same seed.
+pub fn soup(seed: u64, size: i32, density: f64) -> Vec<Coord> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut cells = Vec::new();
+    for y in 0..size {
+        for x in 0..size {
+            if rng.gen::<f64>() < density { cells.push(Coord::new(x, y)) }
+        }
+    }
+    cells
+}
+
+pub struct SoupResult {
+    pub seed: u64,
+    pub generations: u64,
+    // None when the soup was still active after max_generations.
+    pub stabilization: Option<Stabilization>,
+    pub census: Vec<(String, usize)>,
+}
+
+// Runs the soup until it stabilizes, or for at most max_generations, and takes the census of what is left.
+pub fn run(seed: u64, options: &SoupOptions) -> SoupResult {
+    let mut universe: Universe = soup(seed, options.size, options.density).into_iter().collect();
+    let mut detector = StabilizationDetector::new();
+    let mut generations = 0;
+    let mut stabilization = None;
+    while generations < options.max_generations {
+        stabilization = detector.observe(generations, &universe, &options.rule);
+        if stabilization.is_some() { break }
+        universe = universe.step(&options.rule);
+        generations += 1;
+    }
+    SoupResult { seed, generations, stabilization, census: census::census(&universe, &options.rule, &options.census) }
+}
+
+// Objects found over many soups, with a few of the seeds that produced each one.
+#[derive(Default)]
+pub struct Tally {
+    pub soups: u64,
+    // Soups that had not stabilized when they were stopped.
+    pub unstable: u64,
+    pub counts: HashMap<String, u64>,
+    pub samples: HashMap<String, Vec<u64>>,
+}
+
+impl Tally {
+    pub fn new() -> Tally {
+        Tally::default()
+    }
+
+    pub fn add(&mut self, result: &SoupResult) {
+        self.soups += 1;
+        if result.stabilization.is_none() { self.unstable += 1 }
+        for (name, count) in &result.census {
+            *self.counts.entry(name.clone()).or_insert(0) += *count as u64;
+            let samples = self.samples.entry(name.clone()).or_default();
+            if samples.len() < SAMPLES_PER_OBJECT { samples.push(result.seed) }
+        }
+    }
+
+    // Object counts, most common first.
+    pub fn sorted(&self) -> Vec<(String, u64)> {
+        let mut counts: Vec<(String, u64)> = self.counts.iter().map(|(name, count)| (name.clone(), *count)).collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        counts
+    }
+
+    // Objects found at most threshold times.
+    pub fn rare(&self, threshold: u64) -> Vec<(String, u64)> {
+        self.sorted().into_iter().filter(|(_, count)| *count <= threshold).collect()
+    }
+}
+
+// Saves the seed soups of the rare objects as <dir>/<object>_<seed>.rle, returning the number of files written.
+pub fn save_rare(tally: &Tally, options: &SoupOptions, threshold: u64, dir: &str) -> Result<usize, String> {
+    fs::create_dir_all(dir).map_err(|e| format!("could not create {}: {}", dir, e))?;
+    let mut saved = 0;
+    for (name, _) in tally.rare(threshold) {
+        for seed in tally.samples.get(&name).into_iter().flatten() {
+            let cells = soup(*seed, options.size, options.density);
+            let contents = format!("#C soup {}, contains {}\n{}", seed, name, rle::encode_pattern(&cells, &options.rule));
+            let path = format!("{}/{}_{}.rle", dir, file_name(&name), seed);
+            fs::write(&path, contents).map_err(|e| format!("could not write {}: {}", path, e))?;
+            saved += 1;
+        }
+    }
+    Ok(saved)
+}
+
+fn file_name(name: &str) -> String {
+    name.chars().take(64).map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cells(seed: u64) -> Vec<(i32, i32)> {
+        soup(seed, 16, 0.5).iter().map(|c| (c.x, c.y)).collect()
+    }
+
+    #[test]
+    fn makes_the_same_soup_for_the_same_seed() {
+        assert_eq!(cells(7), cells(7));
+        assert_ne!(cells(7), cells(8));
+        assert!(cells(7).iter().all(|(x, y)| (0..16).contains(x) && (0..16).contains(y)));
+        assert!(soup(7, 16, 0.0).is_empty());
+        assert_eq!(soup(7, 16, 1.0).len(), 256);
+    }
+
+    #[test]
+    fn runs_the_same_soup_to_the_same_result() {
+        let options = SoupOptions {
+            size: 6,
+            density: 0.5,
+            max_generations: 150,
+            rule: Rule::life(),
+            census: CensusOptions { connectivity: census::Connectivity::Moore, merge_pseudo_objects: false },
+        };
+        for seed in 0..3 {
+            let (first, second) = (run(seed, &options), run(seed, &options));
+            assert_eq!((first.generations, first.census), (second.generations, second.census));
+        }
+    }
+
+    #[test]
+    fn tallies_objects_with_a_few_sample_seeds() {
+        let mut tally = Tally::new();
+        for seed in 0..5 {
+            let census = vec![("block".to_string(), 2), ("blinker".to_string(), 1)];
+            tally.add(&SoupResult { seed, generations: 100, stabilization: None, census });
+        }
+        tally.add(&SoupResult { seed: 9, generations: 100, stabilization: None, census: vec![("pulsar".to_string(), 1)] });
+        assert_eq!((tally.soups, tally.unstable), (6, 6));
+        assert_eq!(tally.sorted(), vec![("block".to_string(), 10), ("blinker".to_string(), 5), ("pulsar".to_string(), 1)]);
+        assert_eq!(tally.rare(1), vec![("pulsar".to_string(), 1)]);
+        assert_eq!(tally.samples["block"], vec![0, 1, 2]);
+        assert_eq!(tally.samples["pulsar"], vec![9]);
+    }
+}
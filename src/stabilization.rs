@@ -0,0 +1,246 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use crate::census::{self, CensusOptions, Connectivity};
+use crate::coord::Coord;
+use crate::pattern;
+use crate::period;
+use crate::rule::Rule;
+use crate::universe::Universe;
+
+// Longest period of the debris that is recognized as settled, enough for the twin bees shuttle (p46) and for
+// common oscillators mixed together, such as a pentadecathlon next to blinkers (p30). Debris whose combined period is
+// longer, e.g. a twin bees shuttle next to a pulsar (p138), never counts as settled.
+const MAX_PERIOD: usize = 60;
+// Generations the population has to repeat for before the state itself is checked.
+const WINDOW: usize = 180;
+// Larger objects are not checked for being spaceships, which keeps big guns and puffers cheap to look at.
+const MAX_SHIP_CELLS: usize = 200;
+// Longest period of the spaceships looked for. Every object that isn't one is run this long, so it is kept shorter
+// than the period of the debris.
+const MAX_SHIP_PERIOD: u64 = 30;
+
+// A spaceship found in the universe, with its displacement per period.
+pub struct Ship {
+    pub cells: Vec<Coord>,
+    pub dx: i32,
+    pub dy: i32,
+    pub period: u64,
+}
+
+// A spaceship moving away from everything else, so that it will never interact with the rest again.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Escape {
+    pub name: String,
+    pub direction: &'static str,
+    pub dx: i32,
+    pub dy: i32,
+}
+
+// How a pattern settled: the generation it became periodic at apart from escaping spaceships, the period and
+// population of what stays behind, and the spaceships that left.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Stabilization {
+    pub lifespan: u64,
+    pub period: u64,
+    pub population: usize,
+    pub escapes: Vec<Escape>,
+}
+
+impl Stabilization {
+    pub fn describe(&self) -> String {
+        let mut description = format!("stabilized at generation {}, p{}, {} cells", self.lifespan, self.period, self.population);
+        if !self.escapes.is_empty() { description.push_str(&format!(", escaping: {}", self.describe_escapes())) }
+        description
+    }
+
+    // Escaping spaceships grouped by kind and direction, e.g. "2 glider NE, 1 lightweight spaceship W".
+    pub fn describe_escapes(&self) -> String {
+        let mut counts: BTreeMap<(&str, &str), usize> = BTreeMap::new();
+        for escape in &self.escapes {
+            *counts.entry((&escape.name, escape.direction)).or_insert(0) += 1;
+        }
+        counts.iter().map(|((name, direction), count)| format!("{} {} {}", count, name, direction)).collect::<Vec<_>>().join(", ")
+    }
+}
+
+// Escaping spaceships keep the population periodic, so a pattern is only checked for periodic states, with the
+// escaping spaceships taken out, once its population has repeated for a while.
+pub struct StabilizationDetector {
+    first_generation: u64,
+    // Generations observed so far.
+    observed: usize,
+    // Populations of the last MAX_PERIOD generations, oldest first.
+    populations: VecDeque<usize>,
+    // For each period, the last generation, counted from the first one observed, whose population differs from
+    // the one a period later.
+    mismatches: [Option<usize>; MAX_PERIOD + 1],
+    next_check: usize,
+}
+
+impl StabilizationDetector {
+    pub fn new() -> StabilizationDetector {
+        StabilizationDetector { first_generation: 0, observed: 0, populations: VecDeque::new(), mismatches: [None; MAX_PERIOD + 1], next_check: 0 }
+    }
+
+    pub fn clear(&mut self) {
+        *self = StabilizationDetector::new();
+    }
+
+    // Records the population of the generation, returning how the pattern settled once it has.
+    pub fn observe(&mut self, generation: u64, universe: &Universe, rule: &Rule) -> Option<Stabilization> {
+        if self.observed == 0 { self.first_generation = generation }
+        self.record(universe.len());
+        if self.observed < self.next_check { return None }
+
+        let population_period = self.population_period()?;
+        let (rest, escapes) = split_escapes(universe, rule);
+        let periodicity = match period::detect(&rest, rule, MAX_PERIOD as u64) {
+            Some(p) if p.start == 0 && p.dx == 0 && p.dy == 0 => p,
+            // The population repeated by chance, wait for another window before checking again.
+            _ => {
+                self.next_check = self.observed + WINDOW;
+                return None
+            }
+        };
+
+        Some(Stabilization {
+            lifespan: self.first_generation + self.periodic_since(population_period) as u64,
+            period: periodicity.period,
+            population: rest.len(),
+            escapes,
+        })
+    }
+
+    fn record(&mut self, population: usize) {
+        for period in 1..=MAX_PERIOD.min(self.populations.len()) {
+            if self.populations[self.populations.len() - period] != population {
+                self.mismatches[period] = Some(self.observed - period);
+            }
+        }
+        self.populations.push_back(population);
+        if self.populations.len() > MAX_PERIOD { self.populations.pop_front(); }
+        self.observed += 1;
+    }
+
+    // Shortest period the population has repeated with over the whole window.
+    fn population_period(&self) -> Option<usize> {
+        if self.observed < WINDOW { return None }
+        (1..=MAX_PERIOD).find(|p| self.periodic_since(*p) <= self.observed - WINDOW)
+    }
+
+    // Index of the first observed generation from which the population repeats with the period.
+    fn periodic_since(&self, period: usize) -> usize {
+        self.mismatches[period].map_or(0, |generation| generation + 1)
+    }
+}
+
+impl Default for StabilizationDetector {
+    fn default() -> StabilizationDetector {
+        StabilizationDetector::new()
+    }
+}
+
+// Separates spaceships that are past everything else in the direction they move in from the rest of the universe.
+pub fn split_escapes(universe: &Universe, rule: &Rule) -> (Universe, Vec<Escape>) {
+    let (rest, ships) = escaping_ships(universe, rule);
+    let escapes = ships.iter().map(|ship| Escape {
+        name: census::identify(&ship.cells, rule),
+        direction: direction(ship.dx, ship.dy),
+        dx: ship.dx,
+        dy: ship.dy,
+    }).collect();
+    (rest, escapes)
+}
+
+// Like split_escapes, keeping the cells and period of the escaping spaceships.
+pub fn escaping_ships(universe: &Universe, rule: &Rule) -> (Universe, Vec<Ship>) {
+    let options = CensusOptions { connectivity: Connectivity::Moore, merge_pseudo_objects: false };
+    let mut ships = Vec::new();
+    let mut rest = Universe::new();
+    for object in census::objects(universe, rule, &options) {
+        if object.len() > MAX_SHIP_CELLS {
+            rest.extend(object);
+            continue
+        }
+        let object_universe: Universe = object.iter().copied().collect();
+        match period::detect(&object_universe, rule, MAX_SHIP_PERIOD) {
+            Some(p) if p.start == 0 && (p.dx != 0 || p.dy != 0) => ships.push(Ship { cells: object, dx: p.dx, dy: p.dy, period: p.period }),
+            _ => rest.extend(object),
+        }
+    }
+
+    let bounds = rest.bounding_box();
+    let mut escaping = Vec::new();
+    for ship in ships {
+        if moving_away(&ship, bounds) {
+            escaping.push(ship);
+        } else {
+            rest.extend(ship.cells);
+        }
+    }
+    (rest, escaping)
+}
+
+fn moving_away(ship: &Ship, bounds: Option<(Coord, Coord)>) -> bool {
+    let (rest_min, rest_max) = match bounds { Some(b) => b, None => return true };
+    let (min, max) = match pattern::bounding_box(&ship.cells) { Some(b) => b, None => return false };
+    let (dx, dy) = (ship.dx, ship.dy);
+    (dx > 0 && min.x > rest_max.x) || (dx < 0 && max.x < rest_min.x) || (dy > 0 && min.y > rest_max.y) || (dy < 0 && max.y < rest_min.y)
+}
+
+// Compass direction of a displacement, with north up.
+pub fn direction(dx: i32, dy: i32) -> &'static str {
+    match (dx.signum(), dy.signum()) {
+        (0, -1) => "N",
+        (1, -1) => "NE",
+        (1, 0) => "E",
+        (1, 1) => "SE",
+        (0, 1) => "S",
+        (-1, 1) => "SW",
+        (-1, 0) => "W",
+        (-1, -1) => "NW",
+        _ => "",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rle;
+
+    fn stabilize(pattern: &str, max_generations: u64) -> Option<Stabilization> {
+        let rule = Rule::life();
+        let mut universe: Universe = rle::parse_pattern(pattern).unwrap().into_iter().collect();
+        let mut detector = StabilizationDetector::new();
+        for generation in 0..max_generations {
+            if let Some(stabilization) = detector.observe(generation, &universe, &rule) { return Some(stabilization) }
+            universe = universe.step(&rule);
+        }
+        None
+    }
+
+    #[test]
+    fn still_lifes_are_stable_from_the_start() {
+        let block = stabilize("2o$2o!", 1000).unwrap();
+        assert_eq!((block.lifespan, block.period, block.population), (0, 1, 4));
+        assert!(block.escapes.is_empty());
+        let blinker = stabilize("3o!", 1000).unwrap();
+        assert_eq!((blinker.lifespan, blinker.period), (0, 2));
+    }
+
+    #[test]
+    fn the_r_pentomino_stabilizes_at_generation_1103() {
+        let r_pentomino = stabilize("b2o$2o$bo!", 2000).unwrap();
+        assert_eq!((r_pentomino.lifespan, r_pentomino.period, r_pentomino.population), (1103, 2, 86));
+        assert_eq!(r_pentomino.escapes.len(), 6);
+        assert!(r_pentomino.escapes.iter().all(|escape| escape.name == "glider"));
+    }
+
+    #[test]
+    fn forgets_populations_older_than_the_longest_period() {
+        let mut detector = StabilizationDetector::new();
+        let universe = Universe::new();
+        for generation in 0..1000 { detector.observe(generation, &universe, &Rule::life()); }
+        assert_eq!(detector.populations.len(), MAX_PERIOD);
+    }
+}
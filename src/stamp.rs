@@ -0,0 +1,120 @@
+use crate::coord::Coord;
+use crate::pattern;
+use crate::rle;
+use crate::state::{State, Tool};
+
+// How the stamped cells combine with the cells already under them.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum StampMode {
+    // Adds the stamped cells, keeping everything else.
+    Or,
+    // Toggles the cells under the stamped cells.
+    Xor,
+    // Replaces the whole bounding box of the stamp, clearing cells that are dead in it.
+    Copy,
+}
+
+impl StampMode {
+    pub fn next(self) -> StampMode {
+        match self {
+            StampMode::Or => StampMode::Xor,
+            StampMode::Xor => StampMode::Copy,
+            StampMode::Copy => StampMode::Or,
+        }
+    }
+}
+
+// Pattern that follows the cursor while the stamp tool is active, kept with its bounding box at (0, 0).
+pub struct Stamp {
+    pub cells: Vec<Coord>,
+    pub width: i32,
+    pub height: i32,
+    pub mode: StampMode,
+}
+
+impl Stamp {
+    pub fn new(cells: &[Coord], mode: StampMode) -> Stamp {
+        let cells = pattern::normalize(cells);
+        let (width, height) = match pattern::bounding_box(&cells) {
+            Some((_, max)) => (max.x + 1, max.y + 1),
+            None => (0, 0)
+        };
+        Stamp { cells, width, height, mode }
+    }
+
+    pub fn rotate(&mut self) {
+        *self = Stamp::new(&pattern::rotate_cw(&self.cells), self.mode);
+    }
+
+    pub fn flip_horizontal(&mut self) {
+        *self = Stamp::new(&pattern::flip_horizontal(&self.cells), self.mode);
+    }
+
+    pub fn flip_vertical(&mut self) {
+        *self = Stamp::new(&pattern::flip_vertical(&self.cells), self.mode);
+    }
+
+    // Top left corner of the stamp when centered on the given cell.
+    pub fn origin(&self, at: Coord) -> Coord {
+        Coord::new(at.x - self.width / 2, at.y - self.height / 2)
+    }
+
+    // Cells of the stamp centered on the given cell.
+    pub fn cells_at(&self, at: Coord) -> Vec<Coord> {
+        let origin = self.origin(at);
+        pattern::translate(&self.cells, origin.x, origin.y)
+    }
+}
+
+// Makes the cells the current stamp and switches to the stamp tool, keeping the previous paste mode.
+pub fn pick(state: &mut State, cells: &[Coord]) {
+    let mode = state.stamp.as_ref().map(|s| s.mode).unwrap_or(StampMode::Or);
+    state.stamp = Some(Stamp::new(cells, mode));
+    state.stroke = None;
+    state.tool = Tool::Stamp;
+}
+
+pub fn pick_file(state: &mut State, path: &str) -> Result<(), String> {
+    let cells = rle::read_pattern(path)?;
+    pick(state, &cells);
+    Ok(())
+}
+
+pub fn pick_clipboard(state: &mut State) -> Result<(), String> {
+    let clipboard = match &state.clipboard { Some(c) => c, None => return Err("clipboard is empty".to_string()) };
+    let cells = rle::parse_pattern(clipboard)?;
+    pick(state, &cells);
+    Ok(())
+}
+
+// Selects the stamp tool, starting from the clipboard if nothing was picked yet.
+pub fn toggle_tool(state: &mut State) {
+    if state.tool != Tool::Stamp && state.stamp.is_none() {
+        if let Err(e) = pick_clipboard(state) { println!("nothing to stamp: {}", e) }
+        return
+    }
+    state.toggle_tool(Tool::Stamp);
+}
+
+pub fn place(state: &mut State, at: Coord) {
+    let stamp = match &state.stamp { Some(s) => s, None => return };
+    let cells = stamp.cells_at(at);
+    match stamp.mode {
+        StampMode::Or => {
+            for coord in cells { state.insert_cell(coord); }
+        },
+        StampMode::Xor => {
+            for coord in cells {
+                if !state.remove_cell(&coord) { state.insert_cell(coord); }
+            }
+        },
+        StampMode::Copy => {
+            let origin = stamp.origin(at);
+            let max = Coord::new(origin.x + stamp.width - 1, origin.y + stamp.height - 1);
+            for coord in state.cell_coords.cells_in_rect(origin, max) {
+                state.remove_cell(&coord);
+            }
+            for coord in cells { state.insert_cell(coord); }
+        }
+    }
+}
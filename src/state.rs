@@ -1,6 +1,6 @@
-use std::{collections::HashSet, fmt};
+use std::{collections::{HashMap, VecDeque}, fmt};
 
-use crate::{timestep::TimeStep, coord::Coord};
+use crate::{history, timestep::TimeStep, coord::Coord, selection::Selection, drawing::Stroke, universe::Universe, coloring::RenderMode, rule::Rule, console::Console, library::Library, stamp::Stamp, camera::CameraMode, bookmarks::Bookmark, period::{PeriodDetector, Periodicity}, stabilization::{StabilizationDetector, Stabilization}, population::PopulationHistory, emission::EmissionTracker, predecessor::PendingSearch};
 
 const INITIAL_CAMERA_X: f32 = 390.0;
 const INITIAL_CAMERA_Y: f32 = 390.0;
@@ -8,9 +8,52 @@ const INITIAL_CAMERA_Z: f32 = 1.0;
 const INITIAL_CELL_UPDATE_INTERVAL: f32 = 20.0;
 const INITIAL_CELL_WIDTH: f32 = 1.0;
 const INITIAL_CELL_HEIGHT: f32 = 1.0;
+// Generations kept for the population graph and its export.
+const POPULATION_HISTORY: usize = 100_000;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Tool {
+    Draw,
+    Select,
+    Line,
+    Rectangle,
+    Ellipse,
+    Stamp,
+}
 
 pub struct State {
-    pub cell_coords: HashSet<Coord>,
+    pub cell_coords: Universe,
+    pub rule: Rule,
+    pub pattern_path: Option<String>,
+    pub generation: u64,
+    // Only runs while turned on, it keeps a copy of every recent generation.
+    pub period_detector: Option<PeriodDetector>,
+    pub periodicity: Option<Periodicity>,
+    // Set when the cells or the rule change outside of step, so that the detectors start over at the next step.
+    pub edited: bool,
+    pub stabilization_detector: StabilizationDetector,
+    pub stabilization: Option<Stabilization>,
+    pub population_history: PopulationHistory,
+    pub graph_visible: bool,
+    // Counts spaceships leaving the pattern while Some.
+    pub emission_tracker: Option<EmissionTracker>,
+    pub predecessor_search: Option<PendingSearch>,
+    pub cell_births: HashMap<Coord, u64>,
+    pub heat: HashMap<Coord, u32>,
+    pub changes: VecDeque<Vec<Coord>>,
+    pub render_mode: RenderMode,
+    pub history: Option<Universe>,
+    // Cells alive at the generation the history was reset at.
+    pub history_start: Option<Universe>,
+    pub history_generation: u64,
+    pub minimap_visible: bool,
+    pub fullscreen: bool,
+    pub help_visible: bool,
+    pub hud_visible: bool,
+    pub console: Console,
+    pub library: Library,
+    pub viewport_changed: bool,
+    pub minimap_dragging: bool,
     pub cell_update_interval: f32,
     pub t_since_last_cell_update: f32,
     pub cursor_x: i32,
@@ -29,18 +72,59 @@ pub struct State {
     pub camera_x_acceleration: f32,
     pub camera_y_acceleration: f32,
     pub camera_z_acceleration: f32,
+    pub camera_mode: CameraMode,
+    pub bookmarks: Vec<Bookmark>,
+    pub bookmark_index: usize,
+    pub panning: bool,
+    pub pan_velocity_x: f32,
+    pub pan_velocity_y: f32,
+    // Recent drag motions as event timestamp in milliseconds and distance in pixels, to fling the camera on release.
+    pub pan_motions: VecDeque<(u32, f32, f32)>,
     pub cell_width: f32,
     pub cell_height: f32,
     pub t: f32,
     pub timestep: TimeStep,
     pub fps: u32,
     pub font: Option<sdl2::ttf::Font<'static, 'static>>,
+    pub tool: Tool,
+    pub selection: Option<Selection>,
+    pub selecting: bool,
+    pub clipboard: Option<String>,
+    pub stroke: Option<Stroke>,
+    pub stamp: Option<Stamp>,
 }
 
 impl State {
     pub fn new() -> State {
         State {
-            cell_coords: HashSet::new(),
+            cell_coords: Universe::new(),
+            rule: Rule::life(),
+            pattern_path: None,
+            generation: 0,
+            period_detector: None,
+            periodicity: None,
+            edited: false,
+            stabilization_detector: StabilizationDetector::new(),
+            stabilization: None,
+            population_history: PopulationHistory::new(POPULATION_HISTORY),
+            graph_visible: false,
+            emission_tracker: None,
+            predecessor_search: None,
+            cell_births: HashMap::new(),
+            heat: HashMap::new(),
+            changes: VecDeque::new(),
+            render_mode: RenderMode::Plain,
+            history: None,
+            history_start: None,
+            history_generation: 0,
+            minimap_visible: true,
+            fullscreen: false,
+            help_visible: false,
+            hud_visible: true,
+            console: Console::new(),
+            library: Library::new(),
+            viewport_changed: true,
+            minimap_dragging: false,
             cell_update_interval: INITIAL_CELL_UPDATE_INTERVAL,
             t_since_last_cell_update: 0.0,
             cursor_x: 0,
@@ -59,17 +143,45 @@ impl State {
             camera_x_acceleration: 0.0,
             camera_y_acceleration: 0.0,
             camera_z_acceleration: 0.0,
+            camera_mode: CameraMode::Free,
+            bookmarks: Vec::new(),
+            bookmark_index: 0,
+            panning: false,
+            pan_velocity_x: 0.0,
+            pan_velocity_y: 0.0,
+            pan_motions: VecDeque::new(),
             cell_width: INITIAL_CELL_WIDTH,
             cell_height: INITIAL_CELL_HEIGHT,
             t: 0.0,
             timestep: TimeStep::new(),
             fps: 0,
-            font: None
+            font: None,
+            tool: Tool::Draw,
+            selection: None,
+            selecting: false,
+            clipboard: None,
+            stroke: None,
+            stamp: None,
         }
     }
 
     pub fn reset_cell_coords(&mut self) {
-        self.cell_coords = HashSet::new();
+        self.cell_coords = Universe::new();
+        self.generation = 0;
+        if let Some(detector) = &mut self.period_detector { detector.clear() }
+        self.periodicity = None;
+        self.stabilization_detector.clear();
+        self.stabilization = None;
+        self.population_history.clear();
+        if let Some(tracker) = &mut self.emission_tracker { tracker.clear() }
+        self.cell_births = HashMap::new();
+        self.heat = HashMap::new();
+        self.changes = VecDeque::new();
+    }
+
+    pub fn toggle_tool(&mut self, tool: Tool) {
+        self.stroke = None;
+        self.tool = if self.tool == tool { Tool::Draw } else { tool };
     }
 
     pub fn reset_camera(&mut self) {
@@ -80,26 +192,60 @@ impl State {
         self.cell_height = INITIAL_CELL_HEIGHT;
     }
 
+    // Adds a cell by hand, born at the current generation. Returns whether it was dead.
+    pub fn insert_cell(&mut self, coord: Coord) -> bool {
+        if !self.cell_coords.insert(coord) { return false }
+        self.edit();
+        if self.render_mode.tracks_births() { self.cell_births.insert(coord, self.generation); }
+        history::record(&mut self.history, &[coord]);
+        true
+    }
+
+    // Removes a cell by hand. Returns whether it was live.
+    pub fn remove_cell(&mut self, coord: &Coord) -> bool {
+        if !self.cell_coords.remove(coord) { return false }
+        self.edit();
+        self.cell_births.remove(coord);
+        true
+    }
+
+    pub fn set_rule(&mut self, rule: Rule) {
+        self.rule = rule;
+        self.edit();
+    }
+
+    // What was detected about the evolution so far no longer holds.
+    fn edit(&mut self) {
+        self.edited = true;
+        self.periodicity = None;
+        self.stabilization = None;
+    }
+
+    // Births and heat are only tracked while a render mode shows them, and start over when it is switched on.
+    pub fn set_render_mode(&mut self, render_mode: RenderMode) {
+        self.render_mode = render_mode;
+        if !render_mode.tracks_births() { self.cell_births = HashMap::new() }
+        if !render_mode.tracks_heat() {
+            self.heat = HashMap::new();
+            self.changes = VecDeque::new();
+        }
+    }
+
+    // Generations since the cell was born, cells born while births weren't tracked count as born at generation 0.
+    pub fn age(&self, coord: &Coord) -> u64 {
+        self.generation.saturating_sub(*self.cell_births.get(coord).unwrap_or(&0))
+    }
+
     pub fn is_live(&self, coord: &Coord) -> bool {
         return self.cell_coords.contains(&coord);
     }
 
     pub fn should_live(&self, coord: &Coord) -> bool {
-        match self.neighbor_count(&coord) {
-            2 => { self.is_live(&coord) },
-            3 => { true },
-            _ => { false }
-        }
+        self.cell_coords.should_live(&self.rule, coord)
     }
 
     pub fn neighbor_count(&self, coord: &Coord) -> u8 {
-        let mut count = 0;
-
-        for coord in coord.neighbors() {
-            if self.is_live(&coord) { count += 1 }
-        }
-
-        count
+        self.cell_coords.neighbor_count(coord)
     }
 }
 
@@ -1,10 +1,15 @@
-use std::collections::HashSet;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 
+use crate::camera;
 use crate::coord::Coord;
+use crate::history;
+use crate::library;
+use crate::population::Sample;
+use crate::predecessor;
 use crate::config::Config;
 use crate::state::State;
+use crate::universe::Universe;
 
 pub fn initial_state() -> State {
     let mut state = State::new();
@@ -30,6 +35,9 @@ pub fn initial_state() -> State {
 
 pub fn update(state: &mut State, config: &Config) -> () {
     update_cells(state, config);
+    library::update(state, config);
+    predecessor::update(state);
+    state.console.update();
 }
 
 pub fn update_fps(state: &mut State, config: &Config) -> () {
@@ -46,27 +54,82 @@ fn update_cells(state: &mut State, config: &Config) -> () {
         return
     }
 
-    let mut new_cell_coords = HashSet::new();
+    state.t_since_last_cell_update -= state.cell_update_interval;
+    step(state, config);
+}
 
+// Advances the universe by one generation.
+pub fn step(state: &mut State, config: &Config) {
+    if state.edited {
+        state.edited = false;
+        if let Some(detector) = &mut state.period_detector { detector.clear() }
+        state.stabilization_detector.clear();
+    }
     // TODO: parallelize loop?
-    // state.cell_coords.iter().for_each(|c| {
-    //     if state.should_live(&c) { new_cell_coords.insert(c.clone()); }
-    //     c.neighbors().iter().for_each(|c| {
-    //         if state.should_live(&c) { new_cell_coords.insert(c.clone()); }
-    //     });
-    // });
-    for cell_coord in &state.cell_coords {
-        if state.should_live(&cell_coord) { new_cell_coords.insert(cell_coord.clone()); }
-        for neighbor_coord in cell_coord.neighbors() {
-            if state.should_live(&neighbor_coord) { new_cell_coords.insert(neighbor_coord.clone()); }
+    let new_cell_coords = state.cell_coords.step(&state.rule);
+
+    record_changes(state, config, &new_cell_coords);
+    state.cell_coords = new_cell_coords;
+    // Spaceships are deleted first, so that the detectors only see what is left.
+    if let Some(tracker) = &mut state.emission_tracker {
+        for coord in tracker.observe(state.generation, &mut state.cell_coords, &state.rule) {
+            state.cell_births.remove(&coord);
         }
     }
+    if let Some(detector) = &mut state.period_detector {
+        state.periodicity = detector.observe(state.generation, &state.cell_coords);
+    }
+    if state.stabilization.is_none() {
+        state.stabilization = state.stabilization_detector.observe(state.generation, &state.cell_coords, &state.rule);
+    }
+}
 
-    state.t_since_last_cell_update -= state.cell_update_interval;
-    state.cell_coords = new_cell_coords;
+// Tracks cell births for age coloring and trails, how often each location changed over the last generations while
+// heat is shown, and the population statistics of the new generation.
+fn record_changes(state: &mut State, config: &Config, new_cell_coords: &Universe) {
+    if state.population_history.samples.is_empty() {
+        state.population_history.record(Sample::new(state.generation, &state.cell_coords, 0, 0));
+    }
+    state.generation += 1;
+
+    let tracks_births = state.render_mode.tracks_births();
+    let mut changes = Vec::new();
+    for coord in new_cell_coords.iter() {
+        if !state.cell_coords.contains(&coord) {
+            if tracks_births { state.cell_births.insert(coord, state.generation); }
+            changes.push(coord);
+        }
+    }
+    history::record(&mut state.history, &changes);
+    let births = changes.len();
+    for coord in state.cell_coords.iter() {
+        if !new_cell_coords.contains(&coord) {
+            if tracks_births { state.cell_births.remove(&coord); }
+            changes.push(coord);
+        }
+    }
+
+    let deaths = changes.len() - births;
+    state.population_history.record(Sample::new(state.generation, new_cell_coords, births, deaths));
+
+    if !state.render_mode.tracks_heat() { return }
+    for coord in &changes {
+        *state.heat.entry(*coord).or_insert(0) += 1;
+    }
+    state.changes.push_back(changes);
+    while state.changes.len() > config.heat_generations as usize {
+        for coord in state.changes.pop_front().unwrap_or_default() {
+            if let Some(heat) = state.heat.get_mut(&coord) {
+                *heat -= 1;
+                if *heat == 0 { state.heat.remove(&coord); }
+            }
+        }
+    }
 }
 
 pub fn update_camera(state: &mut State, config: &Config) -> () {
+    camera::steer(state, config);
+
     if state.camera_x_i != 0.0 {
         if state.camera_x_acceleration < config.camera_xy_acceleration_max {
             state.camera_x_acceleration = (state.camera_x_acceleration + config.camera_xy_acceleration).min(config.camera_xy_acceleration_max);
@@ -93,6 +156,13 @@ pub fn update_camera(state: &mut State, config: &Config) -> () {
     }
     state.camera_y += state.camera_y_velocity * state.camera_y_i;
 
+    if !state.panning {
+        state.camera_x += state.pan_velocity_x;
+        state.camera_y += state.pan_velocity_y;
+    }
+    state.pan_velocity_x *= config.pan_friction;
+    state.pan_velocity_y *= config.pan_friction;
+
     if state.camera_z_i != 0.0 {
         if state.camera_z_acceleration < config.camera_z_acceleration_max {
         }
@@ -106,9 +176,30 @@ pub fn update_camera(state: &mut State, config: &Config) -> () {
     let old_cell_width = state.cell_width;
     let old_cell_height = state.cell_height;
 
-    state.cell_width = (state.cell_width + state.camera_z_velocity * state.camera_z_i).min(config.max_cell_width).max(config.min_cell_width);
-    state.cell_height = (state.cell_height + state.camera_z_velocity * state.camera_z_i).min(config.max_cell_height).max(config.min_cell_height);
+    // Below one pixel per cell the zoom steps shrink with the cell size so that zooming out stays gradual.
+    let zoom_step = state.camera_z_velocity * state.camera_z_i * state.cell_width.min(1.0);
+
+    state.cell_width = (state.cell_width + zoom_step).min(config.max_cell_width).max(config.min_cell_width);
+    state.cell_height = (state.cell_height + zoom_step).min(config.max_cell_height).max(config.min_cell_height);
 
     state.camera_x += (state.camera_x / old_cell_width - state.camera_x / state.cell_width + (config.window_width / old_cell_width - config.window_width / state.cell_width) / 2.0) * state.cell_width;
     state.camera_y += (state.camera_y / old_cell_height - state.camera_y / state.cell_height + (config.window_height / old_cell_height - config.window_height / state.cell_height) / 2.0) * state.cell_height;
 }
+
+// Zooms by the given factor while keeping the point (x, y) of the window fixed on the same cell.
+pub fn zoom_at(state: &mut State, config: &Config, factor: f32, x: f32, y: f32) {
+    let cell_x = (x + state.camera_x) / state.cell_width;
+    let cell_y = (y + state.camera_y) / state.cell_height;
+
+    state.cell_width = (state.cell_width * factor).min(config.max_cell_width).max(config.min_cell_width);
+    state.cell_height = (state.cell_height * factor).min(config.max_cell_height).max(config.min_cell_height);
+
+    state.camera_x = cell_x * state.cell_width - x;
+    state.camera_y = cell_y * state.cell_height - y;
+}
+
+// Moves the camera so that the given cell position is in the middle of the window.
+pub fn center_on(state: &mut State, config: &Config, x: f32, y: f32) {
+    state.camera_x = x * state.cell_width - config.window_width / 2.0;
+    state.camera_y = y * state.cell_height - config.window_height / 2.0;
+}
@@ -0,0 +1,162 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs;
+
+use sdl2::pixels::Color;
+
+use crate::config::Config;
+use crate::coord::Coord;
+use crate::state::State;
+use crate::utils;
+
+pub struct SvgOptions {
+    pub grid: bool,
+}
+
+impl SvgOptions {
+    pub fn new(config: &Config) -> SvgOptions {
+        SvgOptions {
+            grid: config.svg_grid,
+        }
+    }
+}
+
+pub fn export_svg(path: &str, state: &State, config: &Config, options: &SvgOptions, cell_color: &dyn Fn(&Coord) -> Color) -> Result<(), String> {
+    let svg = viewport_svg(state, config, options, cell_color);
+    fs::write(path, svg).map_err(|e| e.to_string())?;
+    println!("exported viewport: {}", path);
+
+    Ok(())
+}
+
+// Live cells visible through the camera, merged into one rect per horizontal run of equally colored cells.
+pub fn viewport_svg(state: &State, config: &Config, options: &SvgOptions, cell_color: &dyn Fn(&Coord) -> Color) -> String {
+    let (top_left, bottom_right) = utils::visible_cell_bounds(state, config);
+    let mut rows: BTreeMap<i32, Vec<i32>> = BTreeMap::new();
+    for coord in state.cell_coords.cells_in_rect(top_left, bottom_right) {
+        rows.entry(coord.y).or_default().push(coord.x);
+    }
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">",
+        config.window_width, config.window_height, config.window_width, config.window_height
+    );
+    let _ = writeln!(
+        svg,
+        "<rect width=\"100%\" height=\"100%\" {}/>",
+        fill(config.background_color)
+    );
+
+    for (y, xs) in rows.iter_mut() {
+        xs.sort_unstable();
+        let mut i = 0;
+        while i < xs.len() {
+            let start = xs[i];
+            let color = cell_color(&Coord::new(start, *y));
+            let mut end = start;
+            while i + 1 < xs.len() && xs[i + 1] == end + 1 && cell_color(&Coord::new(xs[i + 1], *y)) == color {
+                end += 1;
+                i += 1;
+            }
+            let _ = writeln!(
+                svg,
+                "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" {}/>",
+                start as f32 * state.cell_width - state.camera_x,
+                *y as f32 * state.cell_height - state.camera_y,
+                (end - start + 1) as f32 * state.cell_width,
+                state.cell_height,
+                fill(color)
+            );
+            i += 1;
+        }
+    }
+
+    if options.grid { write_grid(&mut svg, state, config, top_left, bottom_right) }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn write_grid(svg: &mut String, state: &State, config: &Config, top_left: Coord, bottom_right: Coord) {
+    // Like the rendered grid, skipped below one pixel per cell where it would be a line per pixel.
+    if state.cell_width < 1.0 || state.cell_height < 1.0 { return }
+
+    let color = config.grid_color;
+    let _ = writeln!(
+        svg,
+        "<g stroke=\"{}\" stroke-opacity=\"{}\" stroke-width=\"1\">",
+        hex(color),
+        state.cell_height / config.max_cell_height
+    );
+    for x in top_left.x..=bottom_right.x + 1 {
+        let wx = x as f32 * state.cell_width - state.camera_x;
+        let _ = writeln!(svg, "<line x1=\"{}\" y1=\"0\" x2=\"{}\" y2=\"{}\"/>", wx, wx, config.window_height);
+    }
+    for y in top_left.y..=bottom_right.y + 1 {
+        let wy = y as f32 * state.cell_height - state.camera_y;
+        let _ = writeln!(svg, "<line x1=\"0\" y1=\"{}\" x2=\"{}\" y2=\"{}\"/>", wy, config.window_width, wy);
+    }
+    svg.push_str("</g>\n");
+}
+
+fn fill(color: Color) -> String {
+    if color.a == 255 {
+        format!("fill=\"{}\"", hex(color))
+    } else {
+        format!("fill=\"{}\" fill-opacity=\"{}\"", hex(color), color.a as f32 / 255.0)
+    }
+}
+
+fn hex(color: Color) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state_with(cells: &[(i32, i32)], cell_width: f32) -> State {
+        let mut state = State::new();
+        state.camera_x = 0.0;
+        state.camera_y = 0.0;
+        state.cell_width = cell_width;
+        state.cell_height = cell_width;
+        for (x, y) in cells { state.cell_coords.insert(Coord::new(*x, *y)); }
+        state
+    }
+
+    fn rects(svg: &str) -> Vec<&str> {
+        svg.lines().filter(|l| l.starts_with("<rect x=")).collect()
+    }
+
+    #[test]
+    fn merges_runs_of_cells_in_a_row() {
+        let state = state_with(&[(1, 1), (2, 1), (3, 1), (5, 1), (2, 2)], 10.0);
+        let config = Config::new();
+        let svg = viewport_svg(&state, &config, &SvgOptions { grid: false }, &|_| config.cell_color);
+        let rects = rects(&svg);
+        assert_eq!(rects.len(), 3);
+        assert!(rects[0].starts_with("<rect x=\"10\" y=\"10\" width=\"30\" height=\"10\""));
+        assert!(rects[1].starts_with("<rect x=\"50\" y=\"10\" width=\"10\""));
+        assert!(rects[2].starts_with("<rect x=\"20\" y=\"20\" width=\"10\""));
+    }
+
+    #[test]
+    fn splits_runs_of_different_colors() {
+        let state = state_with(&[(0, 0), (1, 0), (2, 0)], 10.0);
+        let config = Config::new();
+        let color = |c: &Coord| if c.x == 1 { Color::RGB(255, 0, 0) } else { config.cell_color };
+        assert_eq!(rects(&viewport_svg(&state, &config, &SvgOptions { grid: false }, &color)).len(), 3);
+    }
+
+    #[test]
+    fn skips_the_grid_below_one_pixel_per_cell() {
+        let config = Config::new();
+        let options = SvgOptions { grid: true };
+        let lines = |svg: &str| svg.lines().filter(|l| l.starts_with("<line")).count();
+        assert!(lines(&viewport_svg(&state_with(&[], 10.0), &config, &options, &|_| config.cell_color)) > 0);
+        assert_eq!(lines(&viewport_svg(&state_with(&[], 0.5), &config, &options, &|_| config.cell_color)), 0);
+    }
+}
@@ -0,0 +1,190 @@
+use std::collections::HashMap;
+
+use crate::coord::Coord;
+use crate::rule::Rule;
+
+// Tiles are 16x16 so that each row of a tile fits in a u16.
+pub const TILE_SIZE: i32 = 16;
+
+#[derive(Clone, Copy, Default)]
+pub struct Tile {
+    pub rows: [u16; TILE_SIZE as usize],
+    pub population: u32,
+}
+
+impl Tile {
+    pub fn is_live(&self, x: i32, y: i32) -> bool {
+        self.rows[y as usize] & (1 << x) != 0
+    }
+
+    // Smallest and largest live cell offsets within the tile.
+    pub fn bounding_box(&self) -> Option<(Coord, Coord)> {
+        let min_y = self.rows.iter().position(|row| *row != 0)?;
+        let max_y = self.rows.iter().rposition(|row| *row != 0)?;
+        let bits = self.rows.iter().fold(0, |bits, row| bits | row);
+        Some((
+            Coord::new(bits.trailing_zeros() as i32, min_y as i32),
+            Coord::new(TILE_SIZE - 1 - bits.leading_zeros() as i32, max_y as i32)
+        ))
+    }
+
+    // Live cells of the tile at the given tile coordinate, in universe coordinates.
+    pub fn cells(&self, tile_coord: Coord) -> impl Iterator<Item = Coord> + '_ {
+        let origin = Coord::new(tile_coord.x * TILE_SIZE, tile_coord.y * TILE_SIZE);
+        self.rows.iter().enumerate().flat_map(move |(y, row)| {
+            (0..TILE_SIZE)
+                .filter(move |x| row & (1 << x) != 0)
+                .map(move |x| Coord::new(origin.x + x, origin.y + y as i32))
+        })
+    }
+}
+
+// Set of live cells stored as a sparse map of fixed size tiles, so that the cells and
+// population of any rectangle can be found without walking the whole universe.
+#[derive(Clone, Default)]
+pub struct Universe {
+    tiles: HashMap<Coord, Tile>,
+    population: usize,
+}
+
+impl Universe {
+    pub fn new() -> Universe {
+        Universe {
+            tiles: HashMap::new(),
+            population: 0,
+        }
+    }
+
+    pub fn tile_coord(coord: &Coord) -> Coord {
+        Coord::new(coord.x.div_euclid(TILE_SIZE), coord.y.div_euclid(TILE_SIZE))
+    }
+
+    pub fn insert(&mut self, coord: Coord) -> bool {
+        let (x, y) = (coord.x.rem_euclid(TILE_SIZE), coord.y.rem_euclid(TILE_SIZE));
+        let tile = self.tiles.entry(Universe::tile_coord(&coord)).or_default();
+        if tile.is_live(x, y) { return false }
+        tile.rows[y as usize] |= 1 << x;
+        tile.population += 1;
+        self.population += 1;
+        true
+    }
+
+    pub fn remove(&mut self, coord: &Coord) -> bool {
+        let tile_coord = Universe::tile_coord(coord);
+        let (x, y) = (coord.x.rem_euclid(TILE_SIZE), coord.y.rem_euclid(TILE_SIZE));
+        let tile = match self.tiles.get_mut(&tile_coord) { Some(t) => t, None => return false };
+        if !tile.is_live(x, y) { return false }
+        tile.rows[y as usize] &= !(1 << x);
+        tile.population -= 1;
+        self.population -= 1;
+        if tile.population == 0 { self.tiles.remove(&tile_coord); }
+        true
+    }
+
+    pub fn contains(&self, coord: &Coord) -> bool {
+        match self.tiles.get(&Universe::tile_coord(coord)) {
+            Some(tile) => tile.is_live(coord.x.rem_euclid(TILE_SIZE), coord.y.rem_euclid(TILE_SIZE)),
+            None => false
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.population
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.population == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.tiles.clear();
+        self.population = 0;
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Coord> + '_ {
+        self.tiles.iter().flat_map(|(tile_coord, tile)| tile.cells(*tile_coord))
+    }
+
+    pub fn bounding_box(&self) -> Option<(Coord, Coord)> {
+        let mut bounds: Option<(Coord, Coord)> = None;
+        for (tile_coord, tile) in self.tiles.iter() {
+            let (tile_min, tile_max) = match tile.bounding_box() { Some(b) => b, None => continue };
+            let min = Coord::new(tile_coord.x * TILE_SIZE + tile_min.x, tile_coord.y * TILE_SIZE + tile_min.y);
+            let max = Coord::new(tile_coord.x * TILE_SIZE + tile_max.x, tile_coord.y * TILE_SIZE + tile_max.y);
+            bounds = Some(match bounds {
+                Some((a, b)) => (Coord::new(a.x.min(min.x), a.y.min(min.y)), Coord::new(b.x.max(max.x), b.y.max(max.y))),
+                None => (min, max)
+            });
+        }
+        bounds
+    }
+
+    pub fn neighbor_count(&self, coord: &Coord) -> u8 {
+        coord.neighbors().iter().filter(|c| self.contains(c)).count() as u8
+    }
+
+    pub fn should_live(&self, rule: &Rule, coord: &Coord) -> bool {
+        rule.next_state(self.contains(coord), self.neighbor_count(coord))
+    }
+
+    // The next generation under the given rule.
+    pub fn step(&self, rule: &Rule) -> Universe {
+        let mut next = Universe::new();
+        for coord in self.iter() {
+            if self.should_live(rule, &coord) { next.insert(coord); }
+            for neighbor in coord.neighbors() {
+                if self.should_live(rule, &neighbor) { next.insert(neighbor); }
+            }
+        }
+        next
+    }
+
+    // Non-empty tiles overlapping the rectangle between the two corners (inclusive).
+    pub fn tiles_in_rect(&self, min: Coord, max: Coord) -> Vec<(Coord, &Tile)> {
+        let tile_min = Universe::tile_coord(&min);
+        let tile_max = Universe::tile_coord(&max);
+        let slots = (tile_max.x - tile_min.x + 1) as i64 * (tile_max.y - tile_min.y + 1) as i64;
+
+        if slots < self.tiles.len() as i64 {
+            let mut tiles = Vec::new();
+            for ty in tile_min.y..=tile_max.y {
+                for tx in tile_min.x..=tile_max.x {
+                    let tile_coord = Coord::new(tx, ty);
+                    if let Some(tile) = self.tiles.get(&tile_coord) { tiles.push((tile_coord, tile)) }
+                }
+            }
+            tiles
+        } else {
+            self.tiles
+                .iter()
+                .filter(|(c, _)| c.x >= tile_min.x && c.x <= tile_max.x && c.y >= tile_min.y && c.y <= tile_max.y)
+                .map(|(c, tile)| (*c, tile))
+                .collect()
+        }
+    }
+
+    // Live cells inside the rectangle between the two corners (inclusive).
+    pub fn cells_in_rect(&self, min: Coord, max: Coord) -> Vec<Coord> {
+        self.tiles_in_rect(min, max)
+            .into_iter()
+            .flat_map(|(tile_coord, tile)| tile.cells(tile_coord))
+            .filter(|c| c.x >= min.x && c.x <= max.x && c.y >= min.y && c.y <= max.y)
+            .collect()
+    }
+}
+
+impl FromIterator<Coord> for Universe {
+    fn from_iter<I: IntoIterator<Item = Coord>>(iter: I) -> Universe {
+        let mut universe = Universe::new();
+        universe.extend(iter);
+        universe
+    }
+}
+
+impl Extend<Coord> for Universe {
+    fn extend<I: IntoIterator<Item = Coord>>(&mut self, iter: I) {
+        for coord in iter {
+            self.insert(coord);
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{state::State, coord::Coord};
+use crate::{state::State, coord::Coord, config::Config};
 
 pub fn game_coord(x: f32, y: f32, state: &State) -> Coord {
     let mut x = x + state.camera_x;
@@ -11,3 +11,14 @@ pub fn game_coord(x: f32, y: f32, state: &State) -> Coord {
 pub fn lerp(start: f32, end: f32, t: f32) -> f32 {
     start * (1.0 - t) + end * t
 }
+
+pub fn visible_cell_bounds(state: &State, config: &Config) -> (Coord, Coord) {
+    let top_left = game_coord(0.0, 0.0, state);
+    let bottom_right = game_coord(config.window_width - 1.0, config.window_height - 1.0, state);
+    (top_left, bottom_right)
+}
+
+// Converts window coordinates reported by SDL to drawable pixels, which differ on HiDPI displays.
+pub fn to_pixels(v: i32, config: &Config) -> i32 {
+    (v as f32 * config.pixel_density).round() as i32
+}